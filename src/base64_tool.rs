@@ -1,96 +1,1405 @@
-use base64::{engine::general_purpose, Engine};
-use clap::Parser;
-use std::fs;
-use std::io::{self, Read, Write};
+use base64::alphabet;
+use base64::engine::{general_purpose, DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use base64::Engine;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use clap::{Parser, Subcommand};
+use colored::*;
+use flate2::read::{MultiGzDecoder, ZlibDecoder};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Bytes read per block.
+const BLOCK_SIZE: usize = 48 * 1024;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about = "Base64 encode/decode tool", long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
-    #[arg(help = "Input file (use '-' for stdin)")]
-    input: String,
-    
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[arg(required = true, help = "Input file (use '-' for stdin)")]
+    input: Option<String>,
+
     #[arg(short, long, help = "Decode instead of encode")]
     decode: bool,
-    
+
     #[arg(short, long, help = "Output file (default: stdout)")]
     output: Option<String>,
-    
-    #[arg(short, long, help = "URL-safe encoding/decoding")]
+
+    #[arg(short, long, conflicts_with = "encoding", help = "URL-safe encoding/decoding (same as -e base64url)")]
     url_safe: bool,
-    
+
+    #[arg(short, long, value_enum, default_value = "base64", help = "Encoding scheme")]
+    encoding: Encoding,
+
     #[arg(short, long, help = "Wrap encoded output at column N", default_value = "76")]
     wrap: usize,
+
+    #[arg(long, requires = "decode", conflicts_with = "lenient", help = "Reject non-canonical padding, trailing bits and lowercase base32")]
+    strict: bool,
+
+    #[arg(long, requires = "decode", help = "Accept either base64 alphabet, missing padding and trailing bits")]
+    lenient: bool,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["encoding", "url_safe", "strict", "lenient"],
+        help = "Detect and peel nested encodings and compression until plaintext (reads the whole input)"
+    )]
+    auto: bool,
+
+    #[arg(long, conflicts_with_all = ["encoding", "url_safe", "decode", "auto"], help = "Dump input as xxd-style hex")]
+    hexdump: bool,
+
+    #[arg(short, long, requires = "hexdump", default_value = "16", value_parser = clap::value_parser!(u16).range(1..=256), help = "Bytes per hexdump line")]
+    cols: u16,
+
+    #[arg(short, long, requires = "hexdump", default_value = "2", help = "Bytes per hexdump group (0 for no grouping)")]
+    group: usize,
+
+    #[arg(short, long, conflicts_with_all = ["encoding", "url_safe", "decode", "auto"], help = "Turn an xxd-style hexdump back into binary")]
+    reverse: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Decode and explain a JWT, PEM certificates/keys or a data: URI
+    Inspect(InspectArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct InspectArgs {
+    #[arg(help = "Input file, '-' for stdin, or the token/URI itself")]
+    input: String,
+
+    #[arg(short, long, conflicts_with = "key_file", help = "Secret to verify an HS256/HS384/HS512 JWT signature")]
+    key: Option<String>,
+
+    #[arg(long, help = "Read the JWT secret from a file")]
+    key_file: Option<String>,
+
+    #[arg(short, long, help = "Write the data: URI payload to this file")]
+    output: Option<String>,
 }
 
-fn wrap_string(s: &str, width: usize) -> String {
-    if width == 0 {
-        return s.to_string();
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Strictness {
+    Strict,
+    Normal,
+    Lenient,
+}
+
+/// Where a byte sits in the original input, for error messages.
+#[derive(Debug, Clone, Copy)]
+struct Position {
+    offset: u64,
+    line: u64,
+    column: u64,
+}
+
+impl Position {
+    fn advance(&mut self, byte: u8) {
+        self.offset += 1;
+        if byte == b'\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
     }
-    
-    s.chars()
-        .collect::<Vec<char>>()
-        .chunks(width)
-        .map(|chunk| chunk.iter().collect::<String>())
-        .collect::<Vec<String>>()
-        .join("\n")
 }
 
-fn main() -> io::Result<()> {
-    let args = Args::parse();
-    
-    // Read input
-    let input_data = if args.input == "-" {
-        let mut buffer = Vec::new();
-        io::stdin().read_to_end(&mut buffer)?;
-        buffer
-    } else {
-        fs::read(&args.input)?
+/// Inserts a newline every `width` bytes, carrying the column across writes.
+struct LineWrapper<W: Write> {
+    inner: W,
+    width: usize,
+    column: usize,
+}
+
+impl<W: Write> LineWrapper<W> {
+    fn new(inner: W, width: usize) -> Self {
+        LineWrapper { inner, width, column: 0 }
+    }
+}
+
+impl<W: Write> Write for LineWrapper<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.width == 0 {
+            return self.inner.write(buf);
+        }
+
+        let mut rest = buf;
+        while !rest.is_empty() {
+            if self.column == self.width {
+                self.inner.write_all(b"\n")?;
+                self.column = 0;
+            }
+            let take = rest.len().min(self.width - self.column);
+            self.inner.write_all(&rest[..take])?;
+            self.column += take;
+            rest = &rest[take..];
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn read_some(reader: &mut impl Read, buffer: &mut [u8]) -> io::Result<usize> {
+    loop {
+        match reader.read(buffer) {
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            result => return result,
+        }
+    }
+}
+
+const BASE32: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const BASE32HEX: &[u8; 32] = b"0123456789ABCDEFGHIJKLMNOPQRSTUV";
+const BASE58: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const Z85: &[u8; 85] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+const HEX: &[u8; 16] = b"0123456789abcdef";
+/// Bytes per uuencoded line, as produced by `uuencode`.
+const UU_LINE: usize = 45;
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+enum Encoding {
+    /// RFC 4648 base64 with padding
+    Base64,
+    /// URL-safe base64 without padding
+    Base64url,
+    /// RFC 4648 base32 (TOTP secrets)
+    Base32,
+    /// RFC 4648 base32 with the extended hex alphabet
+    Base32hex,
+    /// Bitcoin base58 (not streamed: whole input is held in memory)
+    Base58,
+    /// Adobe/btoa ascii85
+    Ascii85,
+    /// ZeroMQ Z85
+    Z85,
+    Hex,
+    Uuencode,
+}
+
+#[derive(Debug)]
+struct DecodeError {
+    /// Index into the data passed to `Encoding::decode`.
+    offset: usize,
+    message: String,
+}
+
+impl DecodeError {
+    fn new(offset: usize, message: impl Into<String>) -> Self {
+        DecodeError { offset, message: message.into() }
+    }
+}
+
+fn alphabet_value(alphabet: &[u8], c: u8) -> Option<u32> {
+    alphabet.iter().position(|&a| a == c).map(|v| v as u32)
+}
+
+fn invalid_char(offset: usize, c: u8) -> DecodeError {
+    let shown = if c.is_ascii_graphic() { format!("'{}'", c as char) } else { format!("byte 0x{:02x}", c) };
+    DecodeError::new(offset, format!("invalid character {}", shown))
+}
+
+fn encode_base32(data: &[u8], alphabet: &[u8; 32], out: &mut Vec<u8>) {
+    for group in data.chunks(5) {
+        let mut block = [0u8; 5];
+        block[..group.len()].copy_from_slice(group);
+        let bits = block.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+        let chars = (group.len() * 8).div_ceil(5);
+        for i in 0..8 {
+            if i < chars {
+                out.push(alphabet[((bits >> (35 - i * 5)) & 31) as usize]);
+            } else {
+                out.push(b'=');
+            }
+        }
+    }
+}
+
+fn decode_base32(
+    data: &[u8],
+    alphabet: &[u8; 32],
+    last: bool,
+    strictness: Strictness,
+    out: &mut Vec<u8>,
+) -> Result<usize, DecodeError> {
+    let usable = if last { data.len() } else { data.len() / 8 * 8 };
+    for (index, group) in data[..usable].chunks(8).enumerate() {
+        let start = index * 8;
+        let chars = group.iter().position(|&c| c == b'=').unwrap_or(group.len());
+        if let Some(pad) = group[chars..].iter().position(|&c| c != b'=') {
+            return Err(invalid_char(start + chars + pad, group[chars + pad]));
+        }
+        if chars < group.len() && start + 8 < usable {
+            return Err(DecodeError::new(start + chars, "padding before the end of input"));
+        }
+        if matches!(chars, 1 | 3 | 6) {
+            return Err(DecodeError::new(start + chars, "truncated base32 group"));
+        }
+        if strictness == Strictness::Strict && group.len() < 8 {
+            return Err(DecodeError::new(start + group.len(), "missing padding"));
+        }
+
+        let mut bits = 0u64;
+        for (i, &c) in group[..chars].iter().enumerate() {
+            let c = if strictness == Strictness::Strict { c } else { c.to_ascii_uppercase() };
+            let value = alphabet_value(alphabet, c).ok_or_else(|| invalid_char(start + i, c))?;
+            bits |= (value as u64) << (35 - i * 5);
+        }
+        let bytes = chars * 5 / 8;
+        if strictness != Strictness::Lenient && bits & ((1 << (40 - bytes * 8)) - 1) != 0 {
+            return Err(DecodeError::new(start + chars - 1, format!("'{}' leaves non-zero trailing bits", group[chars - 1] as char)));
+        }
+        out.extend((0..bytes).map(|i| (bits >> (32 - i * 8)) as u8));
+    }
+    Ok(usable)
+}
+
+/// Encodes 4-byte groups in base 85; a short final group of n bytes becomes n + 1 characters.
+fn encode_base85(data: &[u8], alphabet: impl Fn(u32) -> u8, zero_shortcut: bool, out: &mut Vec<u8>) {
+    for group in data.chunks(4) {
+        let mut block = [0u8; 4];
+        block[..group.len()].copy_from_slice(group);
+        let mut value = u32::from_be_bytes(block);
+        if zero_shortcut && value == 0 && group.len() == 4 {
+            out.push(b'z');
+            continue;
+        }
+
+        let mut digits = [0u8; 5];
+        for digit in digits.iter_mut().rev() {
+            *digit = alphabet(value % 85);
+            value /= 85;
+        }
+        out.extend_from_slice(&digits[..group.len() + 1]);
+    }
+}
+
+fn decode_base85(
+    data: &[u8],
+    last: bool,
+    out: &mut Vec<u8>,
+    value_of: impl Fn(u8) -> Option<u32>,
+    ascii85: bool,
+) -> Result<usize, DecodeError> {
+    let mut group = Vec::with_capacity(5);
+    let mut group_start = 0;
+    let mut index = 0;
+
+    while index < data.len() {
+        let c = data[index];
+        if ascii85 && group.is_empty() {
+            if data[index..].starts_with(b"<~") {
+                index += 2;
+                continue;
+            }
+            if data[index..].starts_with(b"~>") {
+                return Ok(data.len());
+            }
+            if c == b'z' {
+                out.extend_from_slice(&[0; 4]);
+                index += 1;
+                continue;
+            }
+        }
+        if ascii85 && c == b'~' {
+            // Either the `~>` terminator inside a partial group or a stray tilde.
+            if !data[index..].starts_with(b"~>") && (last || index + 1 < data.len()) {
+                return Err(invalid_char(index, c));
+            }
+            break;
+        }
+
+        let value = value_of(c).ok_or_else(|| invalid_char(index, c))?;
+        if group.is_empty() {
+            group_start = index;
+        }
+        group.push(value);
+        index += 1;
+
+        if group.len() == 5 {
+            let value = group.iter().try_fold(0u32, |acc, &v| acc.checked_mul(85)?.checked_add(v));
+            let value = value.ok_or_else(|| DecodeError::new(group_start, "group value exceeds 32 bits"))?;
+            out.extend_from_slice(&value.to_be_bytes());
+            group.clear();
+        }
+    }
+
+    if group.is_empty() {
+        return Ok(index);
+    }
+    if !last && !data[index..].starts_with(b"~>") {
+        return Ok(group_start);
+    }
+    if group.len() == 1 {
+        return Err(DecodeError::new(group_start, "a final group needs at least 2 characters"));
+    }
+    let bytes = group.len() - 1;
+    group.resize(5, 84);
+    let value = group.iter().try_fold(0u32, |acc, &v| acc.checked_mul(85)?.checked_add(v));
+    let value = value.ok_or_else(|| DecodeError::new(group_start, "group value exceeds 32 bits"))?;
+    out.extend_from_slice(&value.to_be_bytes()[..bytes]);
+    Ok(data.len())
+}
+
+fn encode_base58(data: &[u8], out: &mut Vec<u8>) {
+    let zeros = data.iter().take_while(|&&b| b == 0).count();
+    let mut digits: Vec<u8> = Vec::new();
+    for &byte in &data[zeros..] {
+        let mut carry = byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    out.extend(std::iter::repeat_n(b'1', zeros));
+    out.extend(digits.iter().rev().map(|&d| BASE58[d as usize]));
+}
+
+fn decode_base58(data: &[u8], out: &mut Vec<u8>) -> Result<(), DecodeError> {
+    let zeros = data.iter().take_while(|&&c| c == b'1').count();
+    let mut bytes: Vec<u8> = Vec::new();
+    for (index, &c) in data.iter().enumerate().skip(zeros) {
+        let mut carry = alphabet_value(BASE58, c).ok_or_else(|| invalid_char(index, c))?;
+        for byte in bytes.iter_mut() {
+            carry += (*byte as u32) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+
+    out.extend(std::iter::repeat_n(0, zeros));
+    out.extend(bytes.iter().rev());
+    Ok(())
+}
+
+fn uu_char(value: u8) -> u8 {
+    if value == 0 { b'`' } else { value + 32 }
+}
+
+fn encode_uu_line(line: &[u8], out: &mut Vec<u8>) {
+    out.push(uu_char(line.len() as u8));
+    for group in line.chunks(3) {
+        let mut block = [0u8; 3];
+        block[..group.len()].copy_from_slice(group);
+        let bits = ((block[0] as u32) << 16) | ((block[1] as u32) << 8) | block[2] as u32;
+        for shift in [18, 12, 6, 0] {
+            out.push(uu_char(((bits >> shift) & 63) as u8));
+        }
+    }
+    out.push(b'\n');
+}
+
+/// Decodes complete lines, skipping the `begin` header and stopping at `end`.
+fn decode_uu(data: &[u8], last: bool, out: &mut Vec<u8>) -> Result<usize, DecodeError> {
+    let mut consumed = 0;
+    while consumed < data.len() {
+        let rest = &data[consumed..];
+        let (line, next) = match rest.iter().position(|&c| c == b'\n') {
+            Some(end) => (&rest[..end], consumed + end + 1),
+            None if last => (rest, data.len()),
+            None => break,
+        };
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let start = consumed;
+        consumed = next;
+
+        if line.is_empty() || line.starts_with(b"begin ") {
+            continue;
+        }
+        if line == b"end" {
+            return Ok(data.len());
+        }
+
+        let length = ((line[0].wrapping_sub(32)) & 63) as usize;
+        let body = &line[1..];
+        if body.len() < length.div_ceil(3) * 4 {
+            return Err(DecodeError::new(start + line.len(), format!("line is too short for {} bytes", length)));
+        }
+        let mut decoded = Vec::with_capacity(length + 2);
+        for (i, group) in body[..length.div_ceil(3) * 4].chunks(4).enumerate() {
+            if let Some(bad) = group.iter().position(|&c| !(32..=96).contains(&c)) {
+                return Err(invalid_char(start + 1 + i * 4 + bad, group[bad]));
+            }
+            let bits = group.iter().fold(0u32, |acc, &c| (acc << 6) | ((c - 32) & 63) as u32);
+            decoded.extend_from_slice(&[(bits >> 16) as u8, (bits >> 8) as u8, bits as u8]);
+        }
+        out.extend_from_slice(&decoded[..length]);
+    }
+    Ok(consumed)
+}
+
+/// Strict mode wants canonical padding (none for base64url), normal mode takes padding or not,
+/// and lenient mode also ignores trailing bits.
+fn base64_engine(encoding: Encoding, strictness: Strictness) -> GeneralPurpose {
+    let padding = match (strictness, encoding) {
+        (Strictness::Strict, Encoding::Base64url) => DecodePaddingMode::RequireNone,
+        (Strictness::Strict, _) => DecodePaddingMode::RequireCanonical,
+        _ => DecodePaddingMode::Indifferent,
     };
-    
-    // Choose encoder
-    let engine = if args.url_safe {
-        &general_purpose::URL_SAFE_NO_PAD
+    let alphabet = if encoding == Encoding::Base64url && strictness != Strictness::Lenient {
+        &alphabet::URL_SAFE
     } else {
-        &general_purpose::STANDARD
-    };
-    
-    // Process data
-    let output_data = if args.decode {
-        // For decoding, remove whitespace first
-        let input_str = String::from_utf8_lossy(&input_data);
-        let cleaned = input_str.chars()
-            .filter(|c| !c.is_whitespace())
-            .collect::<String>();
-        
-        match engine.decode(cleaned.as_bytes()) {
-            Ok(decoded) => decoded,
+        &alphabet::STANDARD
+    };
+    let config = GeneralPurposeConfig::new()
+        .with_decode_padding_mode(padding)
+        .with_decode_allow_trailing_bits(strictness == Strictness::Lenient);
+    GeneralPurpose::new(alphabet, config)
+}
+
+/// Maps URL-safe characters onto the standard alphabet, refusing input that mixes the two.
+fn normalize_base64(data: &mut [u8], url_safe: &mut Option<bool>) -> Result<(), DecodeError> {
+    for (index, c) in data.iter_mut().enumerate() {
+        let (standard, replacement) = match *c {
+            b'+' | b'/' => (true, *c),
+            b'-' => (false, b'+'),
+            b'_' => (false, b'/'),
+            _ => continue,
+        };
+        if *url_safe.get_or_insert(!standard) == standard {
+            return Err(DecodeError::new(index, "mixes standard and URL-safe base64 alphabets"));
+        }
+        *c = replacement;
+    }
+    Ok(())
+}
+
+impl Encoding {
+    /// uuencode is line-oriented, so its whitespace is significant and it's never re-wrapped.
+    fn line_oriented(&self) -> bool {
+        *self == Encoding::Uuencode
+    }
+
+    /// Encodes a prefix of `data`, returning how many bytes it consumed. With `last`, it consumes everything.
+    fn encode(&self, data: &[u8], last: bool, out: &mut Vec<u8>) -> usize {
+        let group = match self {
+            Encoding::Base64 | Encoding::Base64url => 3,
+            Encoding::Base32 | Encoding::Base32hex => 5,
+            Encoding::Ascii85 | Encoding::Z85 => 4,
+            Encoding::Hex => 1,
+            Encoding::Uuencode => UU_LINE,
+            Encoding::Base58 if last => data.len(),
+            Encoding::Base58 => return 0,
+        };
+        let ready = if last { data.len() } else { data.len() / group * group };
+        let data = &data[..ready];
+
+        match self {
+            Encoding::Base64 => general_purpose::STANDARD.encode(data).bytes().for_each(|b| out.push(b)),
+            Encoding::Base64url => general_purpose::URL_SAFE_NO_PAD.encode(data).bytes().for_each(|b| out.push(b)),
+            Encoding::Base32 => encode_base32(data, BASE32, out),
+            Encoding::Base32hex => encode_base32(data, BASE32HEX, out),
+            Encoding::Base58 => encode_base58(data, out),
+            Encoding::Ascii85 => encode_base85(data, |v| v as u8 + b'!', true, out),
+            Encoding::Z85 => encode_base85(data, |v| Z85[v as usize], false, out),
+            Encoding::Hex => data.iter().for_each(|b| out.extend_from_slice(&[HEX[(b >> 4) as usize], HEX[(b & 15) as usize]])),
+            Encoding::Uuencode => data.chunks(UU_LINE).for_each(|line| encode_uu_line(line, out)),
+        }
+        ready
+    }
+
+    /// Decodes a prefix of `data`, returning how many bytes it consumed. With `last`, it consumes everything.
+    ///
+    /// In lenient mode base64 input is expected to have been normalized to the standard alphabet already.
+    fn decode(&self, data: &[u8], last: bool, strictness: Strictness, out: &mut Vec<u8>) -> Result<usize, DecodeError> {
+        match self {
+            Encoding::Base64 | Encoding::Base64url => {
+                let ready = if last { data.len() } else { data.len() / 4 * 4 };
+                let engine = base64_engine(*self, strictness);
+                engine.decode_vec(&data[..ready], out).map_err(|e| match e {
+                    base64::DecodeError::InvalidByte(offset, b'=') => DecodeError::new(offset, "unexpected padding"),
+                    base64::DecodeError::InvalidByte(offset, c) => invalid_char(offset, c),
+                    base64::DecodeError::InvalidLastSymbol(offset, c) => {
+                        DecodeError::new(offset, format!("'{}' leaves non-zero trailing bits", c as char))
+                    }
+                    base64::DecodeError::InvalidLength => DecodeError::new(ready, "truncated base64 group"),
+                    base64::DecodeError::InvalidPadding => {
+                        let padding = data[..ready].iter().position(|&c| c == b'=');
+                        let message = match padding {
+                            None => "missing padding",
+                            Some(_) if *self == Encoding::Base64url && strictness == Strictness::Strict => "base64url must not be padded",
+                            Some(_) => "non-canonical padding",
+                        };
+                        DecodeError::new(padding.unwrap_or(ready), message)
+                    }
+                })?;
+                Ok(ready)
+            }
+            Encoding::Base32 => decode_base32(data, BASE32, last, strictness, out),
+            Encoding::Base32hex => decode_base32(data, BASE32HEX, last, strictness, out),
+            Encoding::Base58 if last => decode_base58(data, out).map(|_| data.len()),
+            Encoding::Base58 => Ok(0),
+            Encoding::Ascii85 => decode_base85(data, last, out, |c| (b'!'..=b'u').contains(&c).then(|| (c - b'!') as u32), true),
+            Encoding::Z85 => decode_base85(data, last, out, |c| alphabet_value(Z85, c), false),
+            Encoding::Hex => {
+                let ready = data.len() / 2 * 2;
+                if last && ready < data.len() {
+                    return Err(DecodeError::new(ready, "odd number of hex digits"));
+                }
+                for (i, pair) in data[..ready].chunks(2).enumerate() {
+                    let high = (pair[0] as char).to_digit(16).ok_or_else(|| invalid_char(i * 2, pair[0]))?;
+                    let low = (pair[1] as char).to_digit(16).ok_or_else(|| invalid_char(i * 2 + 1, pair[1]))?;
+                    out.push((high << 4 | low) as u8);
+                }
+                Ok(ready)
+            }
+            Encoding::Uuencode => decode_uu(data, last, out),
+        }
+    }
+}
+
+/// Encodes whatever has arrived, holding back bytes that don't complete a group.
+fn encode_stream(reader: &mut impl Read, writer: &mut impl Write, encoding: Encoding) -> io::Result<()> {
+    let mut buffer = vec![0u8; BLOCK_SIZE];
+    let mut pending: Vec<u8> = Vec::new();
+    let mut encoded = Vec::new();
+
+    loop {
+        let read = read_some(reader, &mut buffer)?;
+        pending.extend_from_slice(&buffer[..read]);
+
+        encoded.clear();
+        let consumed = encoding.encode(&pending, read == 0, &mut encoded);
+        writer.write_all(&encoded)?;
+        writer.flush()?;
+        pending.drain(..consumed);
+
+        if read == 0 {
+            return Ok(());
+        }
+    }
+}
+
+/// Decodes complete groups as they arrive and keeps the remainder for the next block.
+/// Errors point at the offending character's offset, line and column in the original input.
+fn decode_stream(reader: &mut impl Read, writer: &mut impl Write, encoding: Encoding, strictness: Strictness) -> io::Result<()> {
+    let mut buffer = vec![0u8; BLOCK_SIZE];
+    let mut pending: Vec<u8> = Vec::new();
+    let mut positions: Vec<Position> = Vec::new();
+    let mut cursor = Position { offset: 0, line: 1, column: 1 };
+    let mut decoded = Vec::new();
+    let mut url_safe = None;
+    let normalize = strictness == Strictness::Lenient && matches!(encoding, Encoding::Base64 | Encoding::Base64url);
+
+    loop {
+        let read = read_some(reader, &mut buffer)?;
+        let fresh = pending.len();
+        for &byte in &buffer[..read] {
+            if encoding.line_oriented() || !byte.is_ascii_whitespace() {
+                pending.push(byte);
+                positions.push(cursor);
+            }
+            cursor.advance(byte);
+        }
+
+        decoded.clear();
+        let result = if normalize {
+            normalize_base64(&mut pending[fresh..], &mut url_safe).map_err(|e| DecodeError { offset: fresh + e.offset, ..e })
+        } else {
+            Ok(())
+        };
+        let result = result.and_then(|_| encoding.decode(&pending, read == 0, strictness, &mut decoded));
+        let consumed = match result {
+            Ok(consumed) => consumed,
             Err(e) => {
-                eprintln!("Error decoding: {}", e);
+                let at = positions.get(e.offset).copied().unwrap_or(cursor);
+                eprintln!("Error decoding: {} at offset {} (line {}, column {})", e.message, at.offset, at.line, at.column);
                 std::process::exit(1);
             }
+        };
+        writer.write_all(&decoded)?;
+        writer.flush()?;
+        pending.drain(..consumed);
+        positions.drain(..consumed);
+
+        if read == 0 {
+            return Ok(());
         }
-    } else {
-        let encoded = engine.encode(&input_data);
-        let wrapped = if args.wrap > 0 {
-            wrap_string(&encoded, args.wrap)
+    }
+}
+
+fn hexdump_line(out: &mut String, offset: u64, line: &[u8], cols: usize, group: usize) {
+    let _ = write!(out, "{:08x}:", offset);
+    for i in 0..cols {
+        if i == 0 || (group > 0 && i % group == 0) {
+            out.push(' ');
+        }
+        match line.get(i) {
+            Some(byte) => {
+                let _ = write!(out, "{:02x}", byte);
+            }
+            None => out.push_str("  "),
+        }
+    }
+    out.push_str("  ");
+    out.extend(line.iter().map(|&b| if (0x20..0x7f).contains(&b) { b as char } else { '.' }));
+    out.push('\n');
+}
+
+/// Writes complete lines as input arrives, holding back a partial line until EOF.
+fn hexdump_stream(reader: &mut impl Read, writer: &mut impl Write, cols: usize, group: usize) -> io::Result<()> {
+    let mut buffer = vec![0u8; BLOCK_SIZE];
+    let mut pending: Vec<u8> = Vec::new();
+    let mut text = String::new();
+    let mut offset = 0u64;
+
+    loop {
+        let read = read_some(reader, &mut buffer)?;
+        pending.extend_from_slice(&buffer[..read]);
+        let ready = if read == 0 { pending.len() } else { pending.len() / cols * cols };
+
+        text.clear();
+        for line in pending[..ready].chunks(cols) {
+            hexdump_line(&mut text, offset, line, cols, group);
+            offset += line.len() as u64;
+        }
+        writer.write_all(text.as_bytes())?;
+        writer.flush()?;
+        pending.drain(..ready);
+
+        if read == 0 {
+            return Ok(());
+        }
+    }
+}
+
+fn reverse_error(line: usize, column: Option<usize>, message: &str) -> ! {
+    match column {
+        Some(column) => eprintln!("Error: line {}, column {}: {}", line, column, message),
+        None => eprintln!("Error: line {}: {}", line, message),
+    }
+    std::process::exit(1);
+}
+
+/// Parses `offset: hex  ascii` lines like `xxd -r`, zero-filling any gap between offsets.
+fn reverse_hexdump(reader: impl Read, writer: &mut impl Write) -> io::Result<()> {
+    let mut position = 0u64;
+    let mut bytes = Vec::new();
+
+    for (index, line) in BufReader::new(reader).split(b'\n').enumerate() {
+        let line = line?;
+        let number = index + 1;
+        let line = line.strip_suffix(b"\r").unwrap_or(&line);
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+
+        let Some(colon) = line.iter().position(|&c| c == b':') else { reverse_error(number, None, "missing offset") };
+        let offset = std::str::from_utf8(&line[..colon]).ok().and_then(|o| u64::from_str_radix(o.trim(), 16).ok());
+        let Some(offset) = offset else { reverse_error(number, Some(1), "invalid offset") };
+
+        // The hex column ends at the double space before the ASCII column.
+        let start = colon + 1 + usize::from(line.get(colon + 1) == Some(&b' '));
+        let rest = &line[start..];
+        let end = rest.windows(2).position(|w| w == b"  ").unwrap_or(rest.len());
+        let digits: Vec<(usize, u8)> = rest[..end].iter().enumerate().filter(|&(_, &c)| c != b' ').map(|(i, &c)| (start + i + 1, c)).collect();
+        if digits.len() % 2 == 1 {
+            reverse_error(number, Some(digits[digits.len() - 1].0), "odd number of hex digits");
+        }
+
+        bytes.clear();
+        for pair in digits.chunks(2) {
+            let mut byte = 0u8;
+            for &(column, c) in pair {
+                let Some(value) = (c as char).to_digit(16) else {
+                    reverse_error(number, Some(column), &format!("invalid hex digit '{}'", c as char))
+                };
+                byte = byte << 4 | value as u8;
+            }
+            bytes.push(byte);
+        }
+
+        if offset < position {
+            reverse_error(number, Some(1), &format!("offset {:08x} goes backwards (already at {:08x})", offset, position));
+        }
+        io::copy(&mut io::repeat(0).take(offset - position), writer)?;
+        writer.write_all(&bytes)?;
+        position = offset + bytes.len() as u64;
+    }
+    Ok(())
+}
+
+/// Layers `--auto` will peel before giving up, so nested encodings can't loop forever.
+const MAX_LAYERS: usize = 32;
+/// Largest output `--auto` will inflate a gzip/zlib layer to, so a decompression bomb can't exhaust memory.
+const MAX_DECOMPRESSED: u64 = 256 * 1024 * 1024;
+
+fn is_text(data: &[u8]) -> bool {
+    match std::str::from_utf8(data) {
+        Ok(text) => text.chars().all(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r')),
+        Err(_) => false,
+    }
+}
+
+fn file_type(data: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "PNG image"),
+        (b"\xff\xd8\xff", "JPEG image"),
+        (b"GIF8", "GIF image"),
+        (b"%PDF-", "PDF document"),
+        (b"PK\x03\x04", "ZIP archive"),
+        (b"\x7fELF", "ELF executable"),
+        (b"MZ", "Windows executable"),
+        (b"\xca\xfe\xba\xbe", "Java class or Mach-O universal binary"),
+        (b"\xcf\xfa\xed\xfe", "Mach-O executable"),
+        (b"7z\xbc\xaf\x27\x1c", "7-Zip archive"),
+        (b"BZh", "bzip2 data"),
+        (b"\xfd7zXZ\x00", "xz data"),
+        (b"\x28\xb5\x2f\xfd", "zstd data"),
+        (b"Rar!\x1a\x07", "RAR archive"),
+        (b"OggS", "Ogg media"),
+        (b"RIFF", "RIFF media"),
+        (b"SQLite format 3\x00", "SQLite database"),
+        (b"\x00asm", "WebAssembly module"),
+    ];
+    SIGNATURES.iter().find(|(magic, _)| data.starts_with(magic)).map(|&(_, name)| name)
+}
+
+fn is_zlib(data: &[u8]) -> bool {
+    data.len() > 2 && data[0] & 0x0f == 8 && data[0] >> 4 <= 7 && u16::from_be_bytes([data[0], data[1]]).is_multiple_of(31)
+}
+
+fn percent_decode(text: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len());
+    let mut escapes = 0;
+    let mut i = 0;
+    while i < text.len() {
+        let hex = text.get(i + 1..i + 3).and_then(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok());
+        match (text[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                escapes += 1;
+                i += 3;
+            }
+            (c, _) => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    (escapes > 0).then_some(out)
+}
+
+/// Tries each text encoding on `data`, returning the first whose output is worth continuing with.
+fn peel_encoding(data: &[u8]) -> Option<(&'static str, Vec<u8>)> {
+    let text: Vec<u8> = data.iter().copied().filter(|b| !b.is_ascii_whitespace()).collect();
+    if text.is_empty() {
+        return None;
+    }
+    let has = |f: fn(&u8) -> bool| text.iter().any(f);
+
+    // Binary output is only believable when the input is long and looks nothing like a word.
+    let mut candidates: Vec<(&'static str, Encoding, bool)> = Vec::new();
+    if text.iter().all(u8::is_ascii_hexdigit) {
+        candidates.push(("hex", Encoding::Hex, text.len() >= 16));
+    }
+    if text.iter().all(|&c| BASE32.contains(&c) || c == b'=') {
+        candidates.push(("base32", Encoding::Base32, text.len() >= 16 && has(|c| c.is_ascii_digit() || *c == b'=')));
+    }
+    let mut base64 = text.clone();
+    let mut url_safe = None;
+    if normalize_base64(&mut base64, &mut url_safe).is_ok() {
+        let name = if url_safe == Some(true) { "base64url" } else { "base64" };
+        let strong = text.len() >= 24 && has(u8::is_ascii_uppercase) && has(u8::is_ascii_lowercase) && has(u8::is_ascii_digit);
+        candidates.push((name, Encoding::Base64, strong));
+    }
+    if text.starts_with(b"<~") {
+        candidates.push(("ascii85", Encoding::Ascii85, true));
+    }
+
+    for (name, encoding, strong) in candidates {
+        let input = if name.starts_with("base64") { &base64 } else { &text };
+        let mut out = Vec::new();
+        if encoding.decode(input, true, Strictness::Normal, &mut out).is_err() || out.is_empty() {
+            continue;
+        }
+        if strong || is_text(&out) || file_type(&out).is_some() || out.starts_with(b"\x1f\x8b") || is_zlib(&out) {
+            return Some((name, out));
+        }
+    }
+
+    percent_decode(data).map(|out| ("percent-encoding", out))
+}
+
+/// Repeatedly decodes layered input, reporting each step on stderr, and returns the innermost payload.
+/// Inflates a gzip/zlib layer, exiting with an error instead of growing past `MAX_DECOMPRESSED`.
+fn decompress(decoder: impl Read, name: &str) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    decoder.take(MAX_DECOMPRESSED + 1).read_to_end(&mut out)?;
+    if out.len() as u64 > MAX_DECOMPRESSED {
+        eprintln!("Error: {} decompressed output exceeds {} bytes", name, MAX_DECOMPRESSED);
+        std::process::exit(1);
+    }
+    Ok(out)
+}
+
+fn auto_decode(mut data: Vec<u8>) -> Vec<u8> {
+    for layer in 1..=MAX_LAYERS {
+        let before = data.len();
+        let (name, decoded) = if data.starts_with(b"\x1f\x8b") {
+            match decompress(MultiGzDecoder::new(&data[..]), "gzip") {
+                Ok(out) => ("gzip", out),
+                Err(e) => {
+                    eprintln!("Result: gzip data that failed to decompress ({})", e);
+                    return data;
+                }
+            }
+        } else if let Some(kind) = file_type(&data) {
+            eprintln!("Result: {} ({} bytes)", kind, data.len());
+            return data;
+        } else if is_zlib(&data) && !is_text(&data) {
+            match decompress(ZlibDecoder::new(&data[..]), "zlib") {
+                Ok(out) => ("zlib", out),
+                Err(_) => {
+                    eprintln!("Result: binary data ({} bytes)", data.len());
+                    return data;
+                }
+            }
+        } else if !is_text(&data) {
+            eprintln!("Result: binary data ({} bytes)", data.len());
+            return data;
         } else {
-            encoded
+            match peel_encoding(&data) {
+                Some(step) => step,
+                None => {
+                    eprintln!("Result: plaintext ({} bytes)", data.len());
+                    return data;
+                }
+            }
         };
-        wrapped.into_bytes()
+
+        eprintln!("Layer {}: {} ({} -> {} bytes)", layer, name, before, decoded.len());
+        data = decoded;
+    }
+
+    eprintln!("Result: stopped after {} layers", MAX_LAYERS);
+    data
+}
+
+fn format_time(timestamp: i64) -> String {
+    match DateTime::from_timestamp(timestamp, 0) {
+        Some(time) => time.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+        None => format!("{} (out of range)", timestamp),
+    }
+}
+
+fn format_span(seconds: i64) -> String {
+    let seconds = seconds.unsigned_abs();
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m {}s", seconds / 60, seconds % 60),
+        3600..=86399 => format!("{}h {}m", seconds / 3600, seconds % 3600 / 60),
+        _ => format!("{}d {}h", seconds / 86400, seconds % 86400 / 3600),
+    }
+}
+
+fn fingerprint(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(":")
+}
+
+fn base64url_decode(segment: &str) -> Option<Vec<u8>> {
+    base64_engine(Encoding::Base64url, Strictness::Normal).decode(segment).ok()
+}
+
+fn inspect_jwt(token: &str, key: Option<&[u8]>) -> bool {
+    let segments: Vec<&str> = token.split('.').collect();
+    let header: serde_json::Value = match base64url_decode(segments[0]).and_then(|h| serde_json::from_slice(&h).ok()) {
+        Some(header) => header,
+        None => {
+            eprintln!("Error: JWT header is not base64url-encoded JSON");
+            std::process::exit(1);
+        }
     };
-    
-    // Write output
-    match args.output {
+    let alg = header["alg"].as_str().unwrap_or("none");
+
+    if segments.len() == 5 {
+        println!("{} ({}, {})", "JWE".bold(), alg, header["enc"].as_str().unwrap_or("?"));
+        println!("{}", "Header:".cyan());
+        println!("{}", serde_json::to_string_pretty(&header).unwrap_or_default());
+        println!("Payload is encrypted ({} bytes of ciphertext)", segments[3].len() * 3 / 4);
+        return true;
+    }
+
+    println!("{} ({})", "JWT".bold(), alg);
+    println!("{}", "Header:".cyan());
+    println!("{}", serde_json::to_string_pretty(&header).unwrap_or_default());
+
+    let claims = base64url_decode(segments[1]).unwrap_or_default();
+    println!("{}", "Claims:".cyan());
+    match serde_json::from_slice::<serde_json::Value>(&claims) {
+        Ok(value) => {
+            println!("{}", serde_json::to_string_pretty(&value).unwrap_or_default());
+            let now = Utc::now().timestamp();
+            for claim in ["iat", "nbf", "exp"] {
+                let Some(time) = value[claim].as_i64() else { continue };
+                let relative = if time <= now {
+                    format!("{} ago", format_span(now - time))
+                } else {
+                    format!("in {}", format_span(time - now))
+                };
+                let line = format!("  {}: {} ({})", claim, format_time(time), relative);
+                let late = (claim == "exp" && time <= now) || (claim == "nbf" && time > now);
+                println!("{}", if late { line.red() } else { line.normal() });
+            }
+        }
+        Err(_) => println!("{}", String::from_utf8_lossy(&claims)),
+    }
+
+    let signature = base64url_decode(segments[2]).unwrap_or_default();
+    let Some(key) = key else {
+        println!("Signature: {} bytes, not verified (pass --key to check {})", signature.len(), alg);
+        return true;
+    };
+    let signed = &token[..segments[0].len() + 1 + segments[1].len()];
+    let valid = match alg {
+        "HS256" => Hmac::<Sha256>::new_from_slice(key).map(|mac| mac.chain_update(signed).verify_slice(&signature).is_ok()),
+        "HS384" => Hmac::<Sha384>::new_from_slice(key).map(|mac| mac.chain_update(signed).verify_slice(&signature).is_ok()),
+        "HS512" => Hmac::<Sha512>::new_from_slice(key).map(|mac| mac.chain_update(signed).verify_slice(&signature).is_ok()),
+        _ => {
+            println!("Signature: {} is not an HMAC algorithm, cannot verify with a shared secret", alg);
+            return false;
+        }
+    };
+    if valid.unwrap_or(false) {
+        println!("Signature: {}", "valid".green());
+        true
+    } else {
+        println!("Signature: {}", "INVALID".red().bold());
+        false
+    }
+}
+
+/// Splits one DER element off the front of `data`, returning its tag, contents and the rest.
+fn der_next(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = data.split_first()?;
+    let (&first, mut rest) = rest.split_first()?;
+    let length = if first < 0x80 {
+        first as usize
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || rest.len() < count {
+            return None;
+        }
+        let length = rest[..count].iter().fold(0usize, |acc, &b| acc << 8 | b as usize);
+        rest = &rest[count..];
+        length
+    };
+    (rest.len() >= length).then(|| (tag, &rest[..length], &rest[length..]))
+}
+
+fn der_children(mut data: &[u8]) -> Vec<(u8, &[u8])> {
+    let mut children = Vec::new();
+    while let Some((tag, contents, rest)) = der_next(data) {
+        children.push((tag, contents));
+        data = rest;
+    }
+    children
+}
+
+fn oid_string(data: &[u8]) -> String {
+    let mut parts = Vec::new();
+    let mut value = 0u64;
+    if data.last().is_none_or(|b| b & 0x80 != 0) {
+        return "invalid OID".into();
+    }
+    for &byte in data {
+        if value >> 57 != 0 {
+            return "invalid OID".into();
+        }
+        value = value << 7 | (byte & 0x7f) as u64;
+        if byte & 0x80 == 0 {
+            if parts.is_empty() {
+                let first = (value / 40).min(2);
+                parts.push(first);
+                parts.push(value - first * 40);
+            } else {
+                parts.push(value);
+            }
+            value = 0;
+        }
+    }
+    parts.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(".")
+}
+
+fn oid_name(oid: &str) -> Option<&'static str> {
+    Some(match oid {
+        "1.2.840.113549.1.1.1" => "RSA",
+        "1.2.840.113549.1.1.10" => "RSASSA-PSS",
+        "1.2.840.10045.2.1" => "EC",
+        "1.2.840.10040.4.1" => "DSA",
+        "1.3.101.110" => "X25519",
+        "1.3.101.111" => "X448",
+        "1.3.101.112" => "Ed25519",
+        "1.3.101.113" => "Ed448",
+        "1.2.840.10045.3.1.7" => "P-256",
+        "1.3.132.0.34" => "P-384",
+        "1.3.132.0.35" => "P-521",
+        "1.3.132.0.10" => "secp256k1",
+        "1.2.840.113549.1.1.5" => "sha1WithRSAEncryption",
+        "1.2.840.113549.1.1.11" => "sha256WithRSAEncryption",
+        "1.2.840.113549.1.1.12" => "sha384WithRSAEncryption",
+        "1.2.840.113549.1.1.13" => "sha512WithRSAEncryption",
+        "1.2.840.10045.4.3.2" => "ecdsa-with-SHA256",
+        "1.2.840.10045.4.3.3" => "ecdsa-with-SHA384",
+        "1.2.840.10045.4.3.4" => "ecdsa-with-SHA512",
+        "2.5.4.3" => "CN",
+        "2.5.4.6" => "C",
+        "2.5.4.7" => "L",
+        "2.5.4.8" => "ST",
+        "2.5.4.10" => "O",
+        "2.5.4.11" => "OU",
+        "1.2.840.113549.1.9.1" => "emailAddress",
+        _ => return None,
+    })
+}
+
+fn oid_label(data: &[u8]) -> String {
+    let oid = oid_string(data);
+    oid_name(&oid).map(str::to_string).unwrap_or(oid)
+}
+
+fn integer_bits(data: &[u8]) -> usize {
+    let data = &data[data.iter().take_while(|&&b| b == 0).count()..];
+    data.first().map_or(0, |&b| data.len() * 8 - b.leading_zeros() as usize)
+}
+
+/// Describes a key from its AlgorithmIdentifier and key material (a public key BIT STRING
+/// or a PKCS#8 private key OCTET STRING).
+fn describe_key(algorithm: &[u8], key: &[u8]) -> String {
+    let parts = der_children(algorithm);
+    let Some(&(0x06, oid)) = parts.first() else { return "unknown key".into() };
+    let name = oid_label(oid);
+    match (name.as_str(), parts.get(1)) {
+        ("EC", Some(&(0x06, curve))) => format!("EC {}", oid_label(curve)),
+        ("RSA" | "RSASSA-PSS", _) => {
+            // Public keys are a BIT STRING with a leading unused-bits byte around SEQUENCE { n, e }
+            // and PKCS#1 private keys are SEQUENCE { version, n, ... }.
+            let key = if key.first() == Some(&0) { &key[1..] } else { key };
+            let sequence = der_next(key).map(|(_, contents, _)| der_children(contents));
+            let modulus = sequence.and_then(|fields| fields.into_iter().find(|&(tag, value)| tag == 0x02 && value.len() > 8));
+            match modulus {
+                Some((_, n)) => format!("{} {}-bit", name, integer_bits(n)),
+                None => name,
+            }
+        }
+        _ => name,
+    }
+}
+
+fn der_name(data: &[u8]) -> String {
+    let mut parts = Vec::new();
+    for (_, set) in der_children(data) {
+        for (_, attribute) in der_children(set) {
+            if let [(0x06, oid), (_, value), ..] = der_children(attribute)[..] {
+                parts.push(format!("{}={}", oid_label(oid), String::from_utf8_lossy(value)));
+            }
+        }
+    }
+    parts.join(", ")
+}
+
+/// Parses a UTCTime (YYMMDDHHMMSSZ) or GeneralizedTime (YYYYMMDDHHMMSSZ), rejecting anything else.
+fn der_time(tag: u8, data: &[u8]) -> Option<i64> {
+    let expected = match tag {
+        0x17 => 13,
+        0x18 => 15,
+        _ => return None,
+    };
+    if data.len() != expected || data.last() != Some(&b'Z') || !data[..expected - 1].iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    let text = std::str::from_utf8(&data[..expected - 1]).ok()?;
+    let text = match tag {
+        0x17 => format!("{}{}", if text.get(..2)?.parse::<u32>().ok()? >= 50 { "19" } else { "20" }, text),
+        _ => text.to_string(),
+    };
+    NaiveDateTime::parse_from_str(&text, "%Y%m%d%H%M%S").ok().map(|time| time.and_utc().timestamp())
+}
+
+fn describe_certificate(der: &[u8]) {
+    let certificate = der_next(der).map(|(_, contents, _)| der_children(contents)).unwrap_or_default();
+    let Some(&(0x30, tbs)) = certificate.first() else {
+        println!("  (not a valid X.509 certificate)");
+        return;
+    };
+    let mut fields = der_children(tbs);
+    if fields.first().is_some_and(|&(tag, _)| tag == 0xa0) {
+        fields.remove(0);
+    }
+    let [(_, serial), (_, signature), (_, issuer), (_, validity), (_, subject), (_, key_info), ..] = fields[..] else {
+        println!("  (truncated certificate)");
+        return;
+    };
+
+    println!("  Subject:   {}", der_name(subject));
+    println!("  Issuer:    {}", der_name(issuer));
+    println!("  Serial:    {}", serial.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(":"));
+    let times: Vec<Option<i64>> = der_children(validity).into_iter().map(|(tag, value)| der_time(tag, value)).collect();
+    if let [Some(not_before), Some(not_after)] = times[..] {
+        let now = Utc::now().timestamp();
+        let line = format!("  Valid:     {} to {}", format_time(not_before), format_time(not_after));
+        if now > not_after {
+            println!("{} {}", line, "(expired)".red());
+        } else if now < not_before {
+            println!("{} {}", line, "(not yet valid)".yellow());
+        } else {
+            println!("{}", line);
+        }
+    } else {
+        println!("  Valid:     {}", "invalid time".red());
+    }
+    if let [(_, algorithm), (_, key), ..] = der_children(key_info)[..] {
+        println!("  Key:       {}", describe_key(algorithm, key));
+    }
+    if let Some(&(0x06, oid)) = der_children(signature).first() {
+        println!("  Signature: {}", oid_label(oid));
+    }
+    println!("  SHA-256:   {}", fingerprint(der));
+}
+
+/// Reads an SSH wire-format string (u32 length prefix) off the front of `data`.
+fn ssh_string<'a>(data: &mut &'a [u8]) -> Option<&'a [u8]> {
+    let length = u32::from_be_bytes(data.get(..4)?.try_into().ok()?) as usize;
+    let value = data.get(4..4 + length)?;
+    *data = &data[4 + length..];
+    Some(value)
+}
+
+fn describe_pem(label: &str, der: &[u8]) {
+    let top = der_next(der).map(|(_, contents, _)| der_children(contents)).unwrap_or_default();
+    match label {
+        "CERTIFICATE" | "TRUSTED CERTIFICATE" => describe_certificate(der),
+        "PUBLIC KEY" => {
+            if let [(_, algorithm), (_, key), ..] = top[..] {
+                println!("  Key:       {} public key (SubjectPublicKeyInfo)", describe_key(algorithm, key));
+            }
+            println!("  SHA-256:   {}", fingerprint(der));
+        }
+        "PRIVATE KEY" => {
+            if let [_, (_, algorithm), (_, key), ..] = top[..] {
+                println!("  Key:       {} private key (PKCS#8)", describe_key(algorithm, key));
+            }
+        }
+        "RSA PRIVATE KEY" | "RSA PUBLIC KEY" => {
+            let kind = if label.contains("PRIVATE") { "private" } else { "public" };
+            let bits = top.iter().find(|&&(tag, value)| tag == 0x02 && value.len() > 8).map(|&(_, n)| integer_bits(n));
+            println!("  Key:       RSA {}-bit {} key (PKCS#1)", bits.unwrap_or(0), kind);
+        }
+        "EC PRIVATE KEY" => {
+            let curve = top.iter().find(|&&(tag, _)| tag == 0xa0).and_then(|&(_, params)| der_next(params));
+            let curve = curve.map_or("unknown curve".into(), |(_, oid, _)| oid_label(oid));
+            println!("  Key:       EC {} private key (SEC 1)", curve);
+        }
+        "ENCRYPTED PRIVATE KEY" => println!("  Key:       encrypted PKCS#8 private key (needs a passphrase)"),
+        "OPENSSH PRIVATE KEY" => {
+            let mut rest = der.strip_prefix(b"openssh-key-v1\0").unwrap_or_default();
+            let cipher = ssh_string(&mut rest).map(String::from_utf8_lossy).unwrap_or_default();
+            // Skip the KDF name and options and the key count to reach the first public key.
+            ssh_string(&mut rest);
+            ssh_string(&mut rest);
+            rest = rest.get(4..).unwrap_or_default();
+            let mut public = ssh_string(&mut rest).unwrap_or_default();
+            let kind = ssh_string(&mut public).map(String::from_utf8_lossy).unwrap_or_default();
+            let encrypted = if cipher == "none" { "unencrypted".normal() } else { format!("encrypted with {}", cipher).yellow() };
+            println!("  Key:       {} OpenSSH private key, {}", kind, encrypted);
+        }
+        _ => println!("  ({} bytes of DER, type not recognized)", der.len()),
+    }
+}
+
+fn inspect_pem(text: &str) {
+    let mut lines = text.lines();
+    let mut count = 0;
+    while let Some(line) = lines.next() {
+        let Some(label) = line.trim().strip_prefix("-----BEGIN ").and_then(|l| l.strip_suffix("-----")) else { continue };
+        let end = format!("-----END {}-----", label);
+        let body: String = lines
+            .by_ref()
+            .take_while(|l| l.trim() != end)
+            .filter(|l| !l.contains(':'))
+            .flat_map(|l| l.chars().filter(|c| !c.is_whitespace()))
+            .collect();
+
+        count += 1;
+        match general_purpose::STANDARD.decode(&body) {
+            Ok(der) => {
+                println!("{} {}: {} ({} bytes)", "PEM block".bold(), count, label.cyan(), der.len());
+                describe_pem(label, &der);
+            }
+            Err(e) => println!("{} {}: {} (invalid base64: {})", "PEM block".bold(), count, label.cyan(), e),
+        }
+    }
+}
+
+fn inspect_data_uri(uri: &str, output: Option<&str>) -> io::Result<()> {
+    let Some((meta, payload)) = uri.strip_prefix("data:").and_then(|rest| rest.split_once(',')) else {
+        eprintln!("Error: data: URI has no ',' separating metadata from the payload");
+        std::process::exit(1);
+    };
+    let (mime, base64) = match meta.strip_suffix(";base64") {
+        Some(mime) => (mime, true),
+        None => (meta, false),
+    };
+    let mime = if mime.is_empty() { "text/plain;charset=US-ASCII" } else { mime };
+
+    let raw = percent_decode(payload.as_bytes()).unwrap_or_else(|| payload.as_bytes().to_vec());
+    let data = if base64 {
+        let mut text: Vec<u8> = raw.into_iter().filter(|b| !b.is_ascii_whitespace()).collect();
+        let mut url_safe = None;
+        let decoded = normalize_base64(&mut text, &mut url_safe).and_then(|_| {
+            let mut out = Vec::new();
+            Encoding::Base64.decode(&text, true, Strictness::Normal, &mut out).map(|_| out)
+        });
+        match decoded {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Error decoding: {} at payload character {}", e.message, e.offset + 1);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        raw
+    };
+
+    println!("{}", "data: URI".bold());
+    println!("  MIME type: {}", mime.cyan());
+    println!("  Encoding:  {}", if base64 { "base64" } else { "percent-encoded" });
+    let kind = file_type(&data).unwrap_or(if is_text(&data) { "text" } else { "binary data" });
+    println!("  Payload:   {} bytes ({})", data.len(), kind);
+
+    match output {
         Some(path) => {
-            fs::write(path, &output_data)?;
+            fs::write(path, &data)?;
+            println!("  Saved to:  {}", path);
         }
-        None => {
-            io::stdout().write_all(&output_data)?;
-            if !args.decode {
-                println!(); // Add newline for encoded output
+        None if is_text(&data) => println!("{}", String::from_utf8_lossy(&data)),
+        None => println!("  Use -o FILE to save the payload"),
+    }
+    Ok(())
+}
+
+fn run_inspect(args: &InspectArgs) -> io::Result<()> {
+    let text = if args.input == "-" {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text)?;
+        text
+    } else if Path::new(&args.input).is_file() {
+        fs::read_to_string(&args.input)?
+    } else {
+        args.input.clone()
+    };
+    let token = text.trim();
+    let token = token.strip_prefix("Authorization:").map_or(token, str::trim);
+    let token = token.strip_prefix("Bearer ").map_or(token, str::trim);
+
+    let key = match (&args.key, &args.key_file) {
+        (Some(key), _) => Some(key.as_bytes().to_vec()),
+        (None, Some(path)) => match fs::read(path) {
+            Ok(mut key) => {
+                while key.last().is_some_and(|b| *b == b'\n' || *b == b'\r') {
+                    key.pop();
+                }
+                Some(key)
             }
+            Err(e) => {
+                eprintln!("Error: cannot read key file {}: {}", path, e);
+                std::process::exit(1);
+            }
+        },
+        (None, None) => None,
+    };
+
+    let segments = token.split('.').count();
+    let jwt_like = (segments == 3 || segments == 5)
+        && token.starts_with("eyJ")
+        && token.bytes().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'));
+
+    if token.starts_with("data:") {
+        inspect_data_uri(token, args.output.as_deref())?;
+    } else if token.contains("-----BEGIN ") {
+        inspect_pem(&text);
+    } else if jwt_like {
+        if !inspect_jwt(token, key.as_deref()) {
+            std::process::exit(1);
         }
+    } else {
+        eprintln!("Error: input is not a JWT, PEM block or data: URI");
+        std::process::exit(1);
     }
-    
     Ok(())
-}
\ No newline at end of file
+}
+
+fn main() -> io::Result<()> {
+    let args = Args::parse();
+    if let Some(Command::Inspect(inspect)) = &args.command {
+        return run_inspect(inspect);
+    }
+    let input = args.input.unwrap_or_default();
+
+    let mut reader: Box<dyn Read> = if input == "-" {
+        Box::new(io::stdin().lock())
+    } else {
+        Box::new(File::open(&input)?)
+    };
+    let writer: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut writer = BufWriter::new(writer);
+
+    let encoding = if args.url_safe { Encoding::Base64url } else { args.encoding };
+
+    if args.reverse {
+        reverse_hexdump(reader, &mut writer)?;
+    } else if args.hexdump {
+        hexdump_stream(&mut reader, &mut writer, args.cols as usize, args.group)?;
+    } else if args.auto {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        writer.write_all(&auto_decode(data))?;
+    } else if args.decode {
+        let strictness = if args.strict {
+            Strictness::Strict
+        } else if args.lenient {
+            Strictness::Lenient
+        } else {
+            Strictness::Normal
+        };
+        decode_stream(&mut reader, &mut writer, encoding, strictness)?;
+    } else if encoding.line_oriented() {
+        let name = Path::new(&input).file_name().map_or("-".into(), |name| name.to_string_lossy());
+        writeln!(writer, "begin 644 {}", name)?;
+        encode_stream(&mut reader, &mut writer, encoding)?;
+        writeln!(writer, "`\nend")?;
+    } else {
+        let mut wrapper = LineWrapper::new(&mut writer, args.wrap);
+        encode_stream(&mut reader, &mut wrapper, encoding)?;
+        if args.output.is_none() {
+            writeln!(writer)?; // Add newline for encoded output
+        }
+    }
+
+    writer.flush()
+}
@@ -1,79 +1,2292 @@
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use blake2::{Blake2b512, Blake2s256};
+use chrono::Utc;
 use clap::Parser;
-use hex;
-use md5;
-use sha2::{Digest, Sha256, Sha512};
-use std::fs::File;
-use std::io::{self, Read};
+use colored::Colorize;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use hmac::{Hmac, Mac};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha1::Sha1;
+use sha2::{Digest, Sha224, Sha256, Sha384, Sha512};
+use sha3::{Sha3_224, Sha3_256, Sha3_384, Sha3_512};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, IsTerminal, Read, Seek, SeekFrom, Write};
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use walkdir::WalkDir;
+use xxhash_rust::xxh3::Xxh3;
+use xxhash_rust::xxh64::Xxh64;
+
+type HmacSha256 = Hmac<Sha256>;
+type HmacSha512 = Hmac<Sha512>;
+
+const BUFFER_SIZE: usize = 1024 * 1024;
+/// Files smaller than this finish too quickly for a progress bar to be useful.
+const PROGRESS_THRESHOLD: u64 = 64 * 1024 * 1024;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+const PROGRESS_WIDTH: usize = 30;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about = "Calculate file hashes", long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[arg(required = true, help = "Files or directories to hash, or checksum files with --check (- for stdin)")]
+    files: Vec<String>,
+
+    #[arg(short, long, help = "Verify files listed in GNU or BSD-tag checksum files")]
+    check: bool,
+
+    #[arg(short, long, requires = "check", help = "Don't print OK lines when checking")]
+    quiet: bool,
+
+    #[arg(
+        short,
+        long,
+        value_enum,
+        value_delimiter = ',',
+        help = "Hash algorithms, e.g. sha256,blake3 (default: md5,sha256,sha512; auto-detected with --check)"
+    )]
+    algorithm: Vec<HashAlgorithm>,
+
+    #[arg(long, help = "Never show the progress bar")]
+    no_progress: bool,
+
+    #[command(flatten)]
+    walk: WalkArgs,
+
+    #[arg(short, long, help = "Number of files to hash in parallel (default: number of cores)")]
+    jobs: Option<usize>,
+
+    #[command(flatten)]
+    key: KeyArgs,
+
+    #[arg(short, long, value_enum, default_value = "hex", help = "Digest output encoding")]
+    encoding: DigestEncoding,
+
+    #[arg(long, help = "Expected digest or HMAC (hex or base64); exits non-zero on mismatch")]
+    verify: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum DigestEncoding {
+    Hex,
+    Base64,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Find duplicate files and report the space they waste
+    Dupes(DupesArgs),
+    /// Record or verify an integrity manifest for a directory tree
+    Manifest {
+        #[command(subcommand)]
+        action: ManifestCommand,
+    },
+    /// Build or check a Merkle tree over fixed-size chunks of a large file
+    Merkle {
+        #[command(subcommand)]
+        action: MerkleCommand,
+    },
+    /// Score similarity (0-100) between files using ssdeep fuzzy hashes
+    Compare(CompareArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct CompareArgs {
+    #[arg(required = true, help = "Files, directories or ssdeep signatures to compare")]
+    inputs: Vec<String>,
+
+    #[command(flatten)]
+    walk: WalkArgs,
+
+    #[arg(short, long, help = "Compare against known hashes (ssdeep CSV or `HASH  name` lines) instead of each other")]
+    known: Option<String>,
+
+    #[arg(short, long, default_value = "1", help = "Only report scores at or above this value (0 lists every pair)")]
+    threshold: u32,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum MerkleCommand {
+    /// Hash a file in chunks and write the tree to a sidecar file
+    Create(MerkleCreateArgs),
+    /// Find the chunks of a (partial or corrupted) copy that don't match the sidecar
+    Verify(MerkleVerifyArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct MerkleCreateArgs {
     #[arg(help = "File to hash")]
     file: String,
-    
-    #[arg(short, long, value_enum, default_value = "all", help = "Hash algorithm")]
+
+    #[arg(short, long, default_value = "1M", value_parser = parse_chunk_size, help = "Chunk size, e.g. 256K, 4M, 1G")]
+    chunk_size: u64,
+
+    #[arg(short, long, value_enum, default_value = "sha256", help = "Hash algorithm")]
+    algorithm: HashAlgorithm,
+
+    #[arg(short, long, help = "Sidecar file to write, or - for stdout (default: FILE.merkle)")]
+    output: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+struct MerkleVerifyArgs {
+    #[arg(help = "File to check")]
+    file: String,
+
+    #[arg(short, long, help = "Sidecar file (default: FILE.merkle)")]
+    tree: Option<String>,
+
+    #[arg(long, help = "Trusted root hash the sidecar must match")]
+    root: Option<String>,
+
+    #[arg(long, help = "Print the result as JSON")]
+    json: bool,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum ManifestCommand {
+    /// Record path, size, mtime, mode and hash of every file under a directory
+    Create(ManifestCreateArgs),
+    /// Compare a directory against a manifest and report what changed
+    Verify(ManifestVerifyArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct ManifestCreateArgs {
+    #[arg(help = "Directory to record")]
+    dir: String,
+
+    #[arg(short, long, help = "Manifest file to write")]
+    output: String,
+
+    #[command(flatten)]
+    walk: WalkArgs,
+
+    #[arg(short, long, value_enum, default_value = "sha256", help = "Hash algorithm")]
+    algorithm: HashAlgorithm,
+
+    #[command(flatten)]
+    key: KeyArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct ManifestVerifyArgs {
+    #[arg(help = "Manifest file to check against")]
+    manifest: String,
+
+    #[arg(help = "Directory to verify (default: the directory recorded in the manifest)")]
+    dir: Option<String>,
+
+    #[command(flatten)]
+    key: KeyArgs,
+}
+
+/// Secret for HMAC signing; reading it from a file keeps it out of shell history.
+#[derive(clap::Args, Debug)]
+struct KeyArgs {
+    #[arg(long, conflicts_with = "hmac_key_file", help = "HMAC key")]
+    hmac_key: Option<String>,
+
+    #[arg(long, help = "Read the HMAC key from a file")]
+    hmac_key_file: Option<String>,
+}
+
+impl KeyArgs {
+    fn load(&self) -> Option<Vec<u8>> {
+        if let Some(key) = &self.hmac_key {
+            return Some(key.as_bytes().to_vec());
+        }
+
+        let path = self.hmac_key_file.as_ref()?;
+        match fs::read(path) {
+            Ok(mut key) => {
+                while key.last().is_some_and(|b| *b == b'\n' || *b == b'\r') {
+                    key.pop();
+                }
+                Some(key)
+            }
+            Err(e) => {
+                eprintln!("Error: Cannot read key file '{}': {}", path, e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// How directories are expanded into files, shared by every mode that walks trees.
+#[derive(clap::Args, Debug)]
+struct WalkArgs {
+    #[arg(short, long, help = "Only hash files whose path relative to the root matches one of these globs")]
+    include: Vec<String>,
+
+    #[arg(short = 'x', long, help = "Skip files and directories matching these globs")]
+    exclude: Vec<String>,
+
+    #[arg(long, value_enum, default_value = "files", help = "How to treat symlinks found in directories")]
+    symlinks: SymlinkPolicy,
+}
+
+#[derive(clap::Args, Debug)]
+struct DupesArgs {
+    #[arg(required = true, help = "Directories to search")]
+    dirs: Vec<String>,
+
+    #[command(flatten)]
+    walk: WalkArgs,
+
+    #[arg(short, long, value_enum, default_value = "blake3", help = "Algorithm for the full-content comparison")]
     algorithm: HashAlgorithm,
+
+    #[arg(long, default_value = "1", help = "Ignore files smaller than this many bytes")]
+    min_size: u64,
+
+    #[arg(short, long, value_enum, default_value = "text", help = "Report format")]
+    format: DupesFormat,
+
+    #[arg(long, value_enum, default_value = "delete", help = "What the generated script does with extra copies")]
+    action: DupesAction,
+
+    #[arg(short, long, help = "Number of files to hash in parallel (default: number of cores)")]
+    jobs: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum DupesFormat {
+    Text,
+    Json,
+    /// A shell script that keeps the first copy of each set
+    Script,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum DupesAction {
+    /// Replace extra copies with hard links to the kept one
+    Hardlink,
+    /// Remove extra copies
+    Delete,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+enum SymlinkPolicy {
+    /// Ignore symlinks entirely
+    Skip,
+    /// Hash symlinked files but don't descend into symlinked directories
+    Files,
+    /// Follow all symlinks, including directories
+    Follow,
 }
 
-#[derive(Debug, Clone, clap::ValueEnum)]
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
 enum HashAlgorithm {
     Md5,
+    Sha1,
+    Sha224,
     Sha256,
+    Sha384,
     Sha512,
+    #[value(name = "sha3-224")]
+    Sha3_224,
+    #[value(name = "sha3-256")]
+    Sha3_256,
+    #[value(name = "sha3-384")]
+    Sha3_384,
+    #[value(name = "sha3-512")]
+    Sha3_512,
+    Blake2b,
+    Blake2s,
+    Blake3,
+    Crc32,
+    Xxh64,
+    Xxh3,
+    /// Context-triggered piecewise hash, compatible with ssdeep
+    Ssdeep,
+    /// Every checksum algorithm above; ssdeep is left out since `-c` can't verify it
     All,
 }
 
-fn calculate_md5(data: &[u8]) -> String {
-    let digest = md5::compute(data);
-    format!("{:x}", digest)
+const DEFAULT_ALGORITHMS: [HashAlgorithm; 3] = [HashAlgorithm::Md5, HashAlgorithm::Sha256, HashAlgorithm::Sha512];
+
+const ALL_ALGORITHMS: [HashAlgorithm; 16] = [
+    HashAlgorithm::Md5,
+    HashAlgorithm::Sha1,
+    HashAlgorithm::Sha224,
+    HashAlgorithm::Sha256,
+    HashAlgorithm::Sha384,
+    HashAlgorithm::Sha512,
+    HashAlgorithm::Sha3_224,
+    HashAlgorithm::Sha3_256,
+    HashAlgorithm::Sha3_384,
+    HashAlgorithm::Sha3_512,
+    HashAlgorithm::Blake2b,
+    HashAlgorithm::Blake2s,
+    HashAlgorithm::Blake3,
+    HashAlgorithm::Crc32,
+    HashAlgorithm::Xxh64,
+    HashAlgorithm::Xxh3,
+];
+
+impl HashAlgorithm {
+    /// Expands `all` and drops repeats, keeping the order given. No selection means the classic trio.
+    fn resolve(selected: &[HashAlgorithm]) -> Vec<HashAlgorithm> {
+        if selected.is_empty() {
+            return DEFAULT_ALGORITHMS.to_vec();
+        }
+
+        let mut algorithms = Vec::new();
+        for algorithm in selected {
+            let expanded: &[HashAlgorithm] = match algorithm {
+                HashAlgorithm::All => &ALL_ALGORITHMS,
+                single => std::slice::from_ref(single),
+            };
+            for algorithm in expanded {
+                if !algorithms.contains(algorithm) {
+                    algorithms.push(*algorithm);
+                }
+            }
+        }
+        algorithms
+    }
+
+    /// Name used in labels and BSD-style tag lines, matching coreutils where it has one.
+    fn label(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Md5 => "MD5",
+            HashAlgorithm::Sha1 => "SHA1",
+            HashAlgorithm::Sha224 => "SHA224",
+            HashAlgorithm::Sha256 => "SHA256",
+            HashAlgorithm::Sha384 => "SHA384",
+            HashAlgorithm::Sha512 => "SHA512",
+            HashAlgorithm::Sha3_224 => "SHA3-224",
+            HashAlgorithm::Sha3_256 => "SHA3-256",
+            HashAlgorithm::Sha3_384 => "SHA3-384",
+            HashAlgorithm::Sha3_512 => "SHA3-512",
+            HashAlgorithm::Blake2b => "BLAKE2b",
+            HashAlgorithm::Blake2s => "BLAKE2s",
+            HashAlgorithm::Blake3 => "BLAKE3",
+            HashAlgorithm::Crc32 => "CRC32",
+            HashAlgorithm::Xxh64 => "XXH64",
+            HashAlgorithm::Xxh3 => "XXH3",
+            HashAlgorithm::Ssdeep => "SSDEEP",
+            HashAlgorithm::All => "ALL",
+        }
+    }
+
+    fn from_tag(tag: &str) -> Option<Self> {
+        ALL_ALGORITHMS
+            .into_iter()
+            .find(|algorithm| algorithm.label().eq_ignore_ascii_case(tag))
+    }
+
+    /// Guesses from the digest length alone; where lengths collide the SHA-1/SHA-2 family wins.
+    fn from_digest_len(len: usize) -> Option<Self> {
+        match len {
+            32 => Some(HashAlgorithm::Md5),
+            40 => Some(HashAlgorithm::Sha1),
+            56 => Some(HashAlgorithm::Sha224),
+            64 => Some(HashAlgorithm::Sha256),
+            96 => Some(HashAlgorithm::Sha384),
+            128 => Some(HashAlgorithm::Sha512),
+            _ => None,
+        }
+    }
+
+    fn hasher(&self) -> Hasher {
+        match self {
+            HashAlgorithm::Md5 => Hasher::Md5(md5::Context::new()),
+            HashAlgorithm::Sha1 => Hasher::Sha1(Sha1::new()),
+            HashAlgorithm::Sha224 => Hasher::Sha224(Sha224::new()),
+            HashAlgorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+            HashAlgorithm::Sha384 => Hasher::Sha384(Sha384::new()),
+            HashAlgorithm::Sha512 => Hasher::Sha512(Sha512::new()),
+            HashAlgorithm::Sha3_224 => Hasher::Sha3_224(Sha3_224::new()),
+            HashAlgorithm::Sha3_256 => Hasher::Sha3_256(Sha3_256::new()),
+            HashAlgorithm::Sha3_384 => Hasher::Sha3_384(Sha3_384::new()),
+            HashAlgorithm::Sha3_512 => Hasher::Sha3_512(Sha3_512::new()),
+            HashAlgorithm::Blake2b => Hasher::Blake2b(Blake2b512::new()),
+            HashAlgorithm::Blake2s => Hasher::Blake2s(Blake2s256::new()),
+            HashAlgorithm::Blake3 => Hasher::Blake3(Box::new(blake3::Hasher::new())),
+            HashAlgorithm::Crc32 => Hasher::Crc32(crc32fast::Hasher::new()),
+            HashAlgorithm::Xxh64 => Hasher::Xxh64(Box::new(Xxh64::new(0))),
+            HashAlgorithm::Xxh3 => Hasher::Xxh3(Box::new(Xxh3::new())),
+            HashAlgorithm::Ssdeep => Hasher::Ssdeep(Box::new(FuzzyHasher::new())),
+            HashAlgorithm::All => unreachable!("`all` is expanded by HashAlgorithm::resolve"),
+        }
+    }
+}
+
+/// An in-progress digest, fed chunk by chunk so memory use doesn't depend on file size.
+enum Hasher {
+    Md5(md5::Context),
+    Sha1(Sha1),
+    Sha224(Sha224),
+    Sha256(Sha256),
+    Sha384(Sha384),
+    Sha512(Sha512),
+    Sha3_224(Sha3_224),
+    Sha3_256(Sha3_256),
+    Sha3_384(Sha3_384),
+    Sha3_512(Sha3_512),
+    Blake2b(Blake2b512),
+    Blake2s(Blake2s256),
+    Blake3(Box<blake3::Hasher>),
+    Crc32(crc32fast::Hasher),
+    Xxh64(Box<Xxh64>),
+    Xxh3(Box<Xxh3>),
+    Ssdeep(Box<FuzzyHasher>),
+    HmacSha256(Box<HmacSha256>),
+    HmacSha512(Box<HmacSha512>),
+}
+
+impl Hasher {
+    fn keyed(algorithm: HashAlgorithm, key: &[u8]) -> Option<Hasher> {
+        match algorithm {
+            HashAlgorithm::Sha256 => HmacSha256::new_from_slice(key).ok().map(|mac| Hasher::HmacSha256(Box::new(mac))),
+            HashAlgorithm::Sha512 => HmacSha512::new_from_slice(key).ok().map(|mac| Hasher::HmacSha512(Box::new(mac))),
+            _ => None,
+        }
+    }
+
+    fn algorithm(&self) -> HashAlgorithm {
+        match self {
+            Hasher::Md5(_) => HashAlgorithm::Md5,
+            Hasher::Sha1(_) => HashAlgorithm::Sha1,
+            Hasher::Sha224(_) => HashAlgorithm::Sha224,
+            Hasher::Sha256(_) => HashAlgorithm::Sha256,
+            Hasher::Sha384(_) => HashAlgorithm::Sha384,
+            Hasher::Sha512(_) => HashAlgorithm::Sha512,
+            Hasher::Sha3_224(_) => HashAlgorithm::Sha3_224,
+            Hasher::Sha3_256(_) => HashAlgorithm::Sha3_256,
+            Hasher::Sha3_384(_) => HashAlgorithm::Sha3_384,
+            Hasher::Sha3_512(_) => HashAlgorithm::Sha3_512,
+            Hasher::Blake2b(_) => HashAlgorithm::Blake2b,
+            Hasher::Blake2s(_) => HashAlgorithm::Blake2s,
+            Hasher::Blake3(_) => HashAlgorithm::Blake3,
+            Hasher::Crc32(_) => HashAlgorithm::Crc32,
+            Hasher::Xxh64(_) => HashAlgorithm::Xxh64,
+            Hasher::Xxh3(_) => HashAlgorithm::Xxh3,
+            Hasher::Ssdeep(_) => HashAlgorithm::Ssdeep,
+            Hasher::HmacSha256(_) => HashAlgorithm::Sha256,
+            Hasher::HmacSha512(_) => HashAlgorithm::Sha512,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Hasher::HmacSha256(_) => "HMAC-SHA256",
+            Hasher::HmacSha512(_) => "HMAC-SHA512",
+            hasher => hasher.algorithm().label(),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Md5(context) => context.consume(data),
+            Hasher::Sha1(hasher) => hasher.update(data),
+            Hasher::Sha224(hasher) => hasher.update(data),
+            Hasher::Sha256(hasher) => hasher.update(data),
+            Hasher::Sha384(hasher) => hasher.update(data),
+            Hasher::Sha512(hasher) => hasher.update(data),
+            Hasher::Sha3_224(hasher) => hasher.update(data),
+            Hasher::Sha3_256(hasher) => hasher.update(data),
+            Hasher::Sha3_384(hasher) => hasher.update(data),
+            Hasher::Sha3_512(hasher) => hasher.update(data),
+            Hasher::Blake2b(hasher) => hasher.update(data),
+            Hasher::Blake2s(hasher) => hasher.update(data),
+            Hasher::Blake3(hasher) => {
+                hasher.update(data);
+            }
+            Hasher::Crc32(hasher) => hasher.update(data),
+            Hasher::Xxh64(hasher) => hasher.update(data),
+            Hasher::Xxh3(hasher) => hasher.update(data),
+            Hasher::Ssdeep(hasher) => hasher.update(data),
+            Hasher::HmacSha256(mac) => mac.update(data),
+            Hasher::HmacSha512(mac) => mac.update(data),
+        }
+    }
+
+    fn finalize_bytes(self) -> Vec<u8> {
+        match self {
+            Hasher::Md5(context) => context.compute().0.to_vec(),
+            Hasher::Sha1(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha224(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha256(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha384(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha512(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha3_224(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha3_256(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha3_384(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha3_512(hasher) => hasher.finalize().to_vec(),
+            Hasher::Blake2b(hasher) => hasher.finalize().to_vec(),
+            Hasher::Blake2s(hasher) => hasher.finalize().to_vec(),
+            Hasher::Blake3(hasher) => hasher.finalize().as_bytes().to_vec(),
+            Hasher::Crc32(hasher) => hasher.finalize().to_be_bytes().to_vec(),
+            Hasher::Xxh64(hasher) => hasher.digest().to_be_bytes().to_vec(),
+            Hasher::Xxh3(hasher) => hasher.digest().to_be_bytes().to_vec(),
+            Hasher::Ssdeep(hasher) => hasher.digest().into_bytes(),
+            Hasher::HmacSha256(mac) => mac.finalize().into_bytes().to_vec(),
+            Hasher::HmacSha512(mac) => mac.finalize().into_bytes().to_vec(),
+        }
+    }
+
+    /// Hex digest; fuzzy hashes are already text and are returned as is.
+    fn finalize(self) -> String {
+        match self {
+            Hasher::Ssdeep(hasher) => hasher.digest(),
+            hasher => hex::encode(hasher.finalize_bytes()),
+        }
+    }
+
+    /// Compares against an expected digest; MACs use the constant-time check from the `hmac` crate.
+    fn verify(self, expected: &[u8]) -> bool {
+        match self {
+            Hasher::HmacSha256(mac) => mac.verify_slice(expected).is_ok(),
+            Hasher::HmacSha512(mac) => mac.verify_slice(expected).is_ok(),
+            hasher => hasher.finalize_bytes() == expected,
+        }
+    }
 }
 
-fn calculate_sha256(data: &[u8]) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(data);
-    hex::encode(hasher.finalize())
+const ROLLING_WINDOW: usize = 7;
+const MIN_BLOCKSIZE: u64 = 3;
+const SPAMSUM_LENGTH: usize = 64;
+const NUM_BLOCKHASHES: usize = 31;
+const HASH_PRIME: u32 = 0x0100_0193;
+const HASH_INIT: u32 = 0x2802_1967;
+const B64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+#[derive(Default)]
+struct RollingHash {
+    window: [u8; ROLLING_WINDOW],
+    h1: u32,
+    h2: u32,
+    h3: u32,
+    n: usize,
+}
+
+impl RollingHash {
+    fn update(&mut self, c: u8) {
+        let c32 = c as u32;
+        self.h2 = self.h2.wrapping_sub(self.h1).wrapping_add(ROLLING_WINDOW as u32 * c32);
+        self.h1 = self.h1.wrapping_add(c32).wrapping_sub(self.window[self.n % ROLLING_WINDOW] as u32);
+        self.window[self.n % ROLLING_WINDOW] = c;
+        self.n += 1;
+        self.h3 = (self.h3 << 5) ^ c32;
+    }
+
+    fn sum(&self) -> u32 {
+        self.h1.wrapping_add(self.h2).wrapping_add(self.h3)
+    }
 }
 
-fn calculate_sha512(data: &[u8]) -> String {
-    let mut hasher = Sha512::new();
-    hasher.update(data);
-    hex::encode(hasher.finalize())
+#[derive(Clone)]
+struct BlockHash {
+    h: u32,
+    half_h: u32,
+    digest: [u8; SPAMSUM_LENGTH],
+    half_digest: u8,
+    len: usize,
 }
 
-fn main() -> io::Result<()> {
-    let args = Args::parse();
-    
-    if !Path::new(&args.file).exists() {
-        eprintln!("Error: File '{}' not found", args.file);
-        std::process::exit(1);
+impl BlockHash {
+    fn new() -> Self {
+        BlockHash {
+            h: HASH_INIT,
+            half_h: HASH_INIT,
+            digest: [0; SPAMSUM_LENGTH],
+            half_digest: 0,
+            len: 0,
+        }
     }
-    
-    let mut file = File::open(&args.file)?;
-    let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
-    
-    let file_size = buffer.len();
-    println!("File: {}", args.file);
-    println!("Size: {} bytes", file_size);
-    println!("---");
-    
-    match args.algorithm {
-        HashAlgorithm::Md5 => {
-            println!("MD5:    {}", calculate_md5(&buffer));
+}
+
+fn block_size(index: usize) -> u64 {
+    MIN_BLOCKSIZE << index
+}
+
+/// Streaming ssdeep (spamsum) hasher. Tracks every candidate block size at once, like libfuzzy,
+/// so input is read a single time and output matches `ssdeep` byte for byte.
+struct FuzzyHasher {
+    roll: RollingHash,
+    blocks: Vec<BlockHash>,
+    start: usize,
+    total: u64,
+}
+
+impl FuzzyHasher {
+    fn new() -> Self {
+        FuzzyHasher {
+            roll: RollingHash::default(),
+            blocks: vec![BlockHash::new()],
+            start: 0,
+            total: 0,
         }
-        HashAlgorithm::Sha256 => {
-            println!("SHA256: {}", calculate_sha256(&buffer));
+    }
+
+    fn try_fork(&mut self) {
+        if self.blocks.len() >= NUM_BLOCKHASHES {
+            return;
         }
-        HashAlgorithm::Sha512 => {
-            println!("SHA512: {}", calculate_sha512(&buffer));
+        let last = &self.blocks[self.blocks.len() - 1];
+        let mut next = BlockHash::new();
+        next.h = last.h;
+        next.half_h = last.half_h;
+        self.blocks.push(next);
+    }
+
+    fn try_reduce(&mut self) {
+        if self.blocks.len() - self.start < 2
+            || block_size(self.start) * SPAMSUM_LENGTH as u64 >= self.total
+            || self.blocks[self.start + 1].len < SPAMSUM_LENGTH / 2
+        {
+            return;
         }
-        HashAlgorithm::All => {
-            println!("MD5:    {}", calculate_md5(&buffer));
-            println!("SHA256: {}", calculate_sha256(&buffer));
-            println!("SHA512: {}", calculate_sha512(&buffer));
+        self.start += 1;
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for &c in data {
+            self.total += 1;
+            self.roll.update(c);
+            let sum = self.roll.sum() as u64;
+
+            for block in &mut self.blocks[self.start..] {
+                block.h = block.h.wrapping_mul(HASH_PRIME) ^ c as u32;
+                block.half_h = block.half_h.wrapping_mul(HASH_PRIME) ^ c as u32;
+            }
+
+            let mut index = self.start;
+            while index < self.blocks.len() {
+                let size = block_size(index);
+                if sum % size != size - 1 {
+                    break;
+                }
+                if self.blocks[index].len == 0 {
+                    self.try_fork();
+                }
+
+                let block = &mut self.blocks[index];
+                block.digest[block.len] = B64[(block.h % 64) as usize];
+                block.half_digest = B64[(block.half_h % 64) as usize];
+                if block.len < SPAMSUM_LENGTH - 1 {
+                    block.len += 1;
+                    block.digest[block.len] = 0;
+                    block.h = HASH_INIT;
+                    if block.len < SPAMSUM_LENGTH / 2 {
+                        block.half_h = HASH_INIT;
+                        block.half_digest = 0;
+                    }
+                } else {
+                    self.try_reduce();
+                }
+                index += 1;
+            }
         }
     }
-    
-    Ok(())
-}
\ No newline at end of file
+
+    fn digest(&self) -> String {
+        let rolling = self.roll.sum();
+        let mut index = self.start;
+        while block_size(index) * (SPAMSUM_LENGTH as u64) < self.total {
+            index += 1;
+        }
+        index = index.min(self.blocks.len() - 1);
+        while index > self.start && self.blocks[index].len < SPAMSUM_LENGTH / 2 {
+            index -= 1;
+        }
+
+        let block = &self.blocks[index];
+        let mut first = block.digest[..block.len].to_vec();
+        if rolling != 0 {
+            first.push(B64[(block.h % 64) as usize]);
+        } else if block.digest[block.len] != 0 {
+            first.push(block.digest[block.len]);
+        }
+
+        let mut second = Vec::new();
+        if let Some(next) = self.blocks.get(index + 1) {
+            second.extend_from_slice(&next.digest[..next.len.min(SPAMSUM_LENGTH / 2 - 1)]);
+            if rolling != 0 {
+                second.push(B64[(next.half_h % 64) as usize]);
+            } else if next.half_digest != 0 {
+                second.push(next.half_digest);
+            }
+        } else if rolling != 0 {
+            second.push(B64[(block.h % 64) as usize]);
+        }
+
+        format!(
+            "{}:{}:{}",
+            block_size(index),
+            String::from_utf8_lossy(&first),
+            String::from_utf8_lossy(&second)
+        )
+    }
+}
+
+/// Collapses runs of more than three identical characters, which carry little information.
+fn eliminate_sequences(s: &[u8]) -> Vec<u8> {
+    let mut result: Vec<u8> = Vec::with_capacity(s.len());
+    for &c in s {
+        if result.len() >= 3 && result[result.len() - 3..].iter().all(|&prev| prev == c) {
+            continue;
+        }
+        result.push(c);
+    }
+    result
+}
+
+fn has_common_substring(a: &[u8], b: &[u8]) -> bool {
+    if a.len() < ROLLING_WINDOW || b.len() < ROLLING_WINDOW {
+        return false;
+    }
+    a.windows(ROLLING_WINDOW).any(|window| b.windows(ROLLING_WINDOW).any(|other| other == window))
+}
+
+/// Levenshtein distance with ssdeep's weights: insert and delete cost 1, substitution 2.
+fn edit_distance(a: &[u8], b: &[u8]) -> u32 {
+    let mut previous: Vec<u32> = (0..=b.len() as u32).collect();
+    let mut current = vec![0u32; b.len() + 1];
+
+    for (i, &ca) in a.iter().enumerate() {
+        current[0] = i as u32 + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == cb { 0 } else { 2 };
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+fn score_strings(a: &[u8], b: &[u8], block_size: u64) -> u32 {
+    if a.len() > SPAMSUM_LENGTH || b.len() > SPAMSUM_LENGTH || !has_common_substring(a, b) {
+        return 0;
+    }
+
+    let distance = edit_distance(a, b) as usize;
+    let scaled = (distance * SPAMSUM_LENGTH / (a.len() + b.len())) * 100 / SPAMSUM_LENGTH;
+    if scaled >= 100 {
+        return 0;
+    }
+    let score = 100 - scaled as u32;
+
+    // Small block sizes can't support high scores for short signatures.
+    if block_size >= ((99 + ROLLING_WINDOW as u64) / ROLLING_WINDOW as u64) * MIN_BLOCKSIZE {
+        return score;
+    }
+    let cap = (block_size / MIN_BLOCKSIZE) as u32 * a.len().min(b.len()) as u32;
+    score.min(cap)
+}
+
+/// Splits an ssdeep signature, rejecting block sizes libfuzzy can't produce (3 * 2^n, n < NUM_BLOCKHASHES).
+fn parse_fuzzy(hash: &str) -> Option<(u64, Vec<u8>, Vec<u8>)> {
+    let mut parts = hash.trim().splitn(3, ':');
+    let block_size: u64 = parts.next()?.parse().ok()?;
+    if !(0..NUM_BLOCKHASHES).any(|index| self::block_size(index) == block_size) {
+        return None;
+    }
+    let first = parts.next()?;
+    let second = parts.next()?;
+    // ssdeep's list format appends `,"filename"` to the signature.
+    let second = second.split(',').next().unwrap_or_default();
+    Some((block_size, eliminate_sequences(first.as_bytes()), eliminate_sequences(second.as_bytes())))
+}
+
+/// Similarity of two ssdeep signatures from 0 (unrelated) to 100 (identical), as `ssdeep -d` reports it.
+fn fuzzy_compare(a: &str, b: &str) -> Option<u32> {
+    let (size_a, a1, a2) = parse_fuzzy(a)?;
+    let (size_b, b1, b2) = parse_fuzzy(b)?;
+
+    if size_a != size_b && size_a * 2 != size_b && size_b * 2 != size_a {
+        return Some(0);
+    }
+    if size_a == size_b && a1 == b1 {
+        return Some(100);
+    }
+
+    let score = if size_a == size_b {
+        score_strings(&a1, &b1, size_a).max(score_strings(&a2, &b2, size_a * 2))
+    } else if size_a == size_b * 2 {
+        score_strings(&a1, &b2, size_a)
+    } else {
+        score_strings(&a2, &b1, size_b)
+    };
+    Some(score)
+}
+
+struct Progress {
+    total: Option<u64>,
+    start: Instant,
+    last_draw: Instant,
+}
+
+impl Progress {
+    fn new(total: Option<u64>) -> Self {
+        let now = Instant::now();
+        Progress { total, start: now, last_draw: now }
+    }
+
+    fn update(&mut self, done: u64) {
+        if self.last_draw.elapsed() < PROGRESS_INTERVAL {
+            return;
+        }
+        self.last_draw = Instant::now();
+        self.draw(done);
+    }
+
+    fn draw(&self, done: u64) {
+        let elapsed = self.start.elapsed().as_secs_f64().max(0.001);
+        let rate = format!("{}/s", format_bytes(done as f64 / elapsed));
+        let line = match self.total {
+            Some(total) if total > 0 => {
+                let fraction = (done as f64 / total as f64).min(1.0);
+                let filled = (fraction * PROGRESS_WIDTH as f64) as usize;
+                format!(
+                    "[{}{}] {:>3.0}% {} / {} {}",
+                    "#".repeat(filled),
+                    "-".repeat(PROGRESS_WIDTH - filled),
+                    fraction * 100.0,
+                    format_bytes(done as f64),
+                    format_bytes(total as f64),
+                    rate
+                )
+            }
+            _ => format!("{} {}", format_bytes(done as f64), rate),
+        };
+        eprint!("\r{:<72}", line);
+        let _ = io::stderr().flush();
+    }
+
+    fn finish(&self, done: u64) {
+        self.draw(done);
+        eprintln!();
+    }
+}
+
+fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Reads `reader` once, feeding every hasher from the same buffer. Returns the byte count.
+fn hash_reader(reader: impl Read, hashers: &mut [Hasher], mut progress: Option<Progress>) -> io::Result<u64> {
+    let mut reader = BufReader::with_capacity(BUFFER_SIZE, reader);
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut total = 0u64;
+
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        for hasher in hashers.iter_mut() {
+            hasher.update(&buffer[..read]);
+        }
+        total += read as u64;
+        if let Some(progress) = progress.as_mut() {
+            progress.update(total);
+        }
+    }
+
+    if let Some(progress) = progress {
+        progress.finish(total);
+    }
+    Ok(total)
+}
+
+fn hash_path(path: &str, algorithms: &[HashAlgorithm], show_progress: bool) -> io::Result<(u64, Vec<Hasher>)> {
+    hash_with(path, algorithms.iter().map(HashAlgorithm::hasher).collect(), show_progress)
+}
+
+fn hash_with(path: &str, mut hashers: Vec<Hasher>, show_progress: bool) -> io::Result<(u64, Vec<Hasher>)> {
+    let size = if path == "-" {
+        let progress = show_progress.then(|| Progress::new(None));
+        hash_reader(io::stdin().lock(), &mut hashers, progress)?
+    } else {
+        let file = File::open(path)?;
+        let length = file.metadata()?.len();
+        let progress = (show_progress && length >= PROGRESS_THRESHOLD).then(|| Progress::new(Some(length)));
+        hash_reader(file, &mut hashers, progress)?
+    };
+
+    Ok((size, hashers))
+}
+
+fn file_digest(path: &str, algorithm: HashAlgorithm) -> io::Result<(u64, String)> {
+    let (size, hashers) = hash_path(path, &[algorithm], false)?;
+    Ok((size, hashers.into_iter().map(Hasher::finalize).collect()))
+}
+
+fn build_globs(patterns: &[String]) -> Option<GlobSet> {
+    if patterns.is_empty() {
+        return None;
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        match Glob::new(pattern) {
+            Ok(glob) => {
+                builder.add(glob);
+            }
+            Err(e) => {
+                eprintln!("Error: Invalid glob '{}': {}", pattern, e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    builder.build().ok()
+}
+
+/// Expands directories into the files beneath them. Globs match paths relative to the directory given.
+fn collect_files(inputs: &[String], walk: &WalkArgs, errors: &mut usize) -> Vec<String> {
+    let include = build_globs(&walk.include);
+    let exclude = build_globs(&walk.exclude);
+    let mut files = Vec::new();
+
+    for input in inputs {
+        if input == "-" || !Path::new(input).is_dir() {
+            files.push(input.clone());
+            continue;
+        }
+
+        let root = Path::new(input);
+        let walker = WalkDir::new(root)
+            .follow_links(walk.symlinks == SymlinkPolicy::Follow)
+            .into_iter()
+            .filter_entry(|entry| {
+                let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+                entry.depth() == 0 || !exclude.as_ref().is_some_and(|globs| globs.is_match(relative))
+            });
+
+        for entry in walker {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    eprintln!("file-hasher: {}", e);
+                    *errors += 1;
+                    continue;
+                }
+            };
+
+            let is_file = if entry.path_is_symlink() && walk.symlinks != SymlinkPolicy::Follow {
+                walk.symlinks == SymlinkPolicy::Files && entry.path().is_file()
+            } else {
+                entry.file_type().is_file()
+            };
+            if !is_file {
+                continue;
+            }
+
+            let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+            if include.as_ref().is_some_and(|globs| !globs.is_match(relative)) {
+                continue;
+            }
+            files.push(entry.path().to_string_lossy().into_owned());
+        }
+    }
+
+    files.sort();
+    files.dedup();
+    files
+}
+
+/// Escapes a path the way coreutils does, flagging the line with a leading backslash.
+fn escape_path(path: &str) -> (&'static str, String) {
+    if path.contains(['\\', '\n', '\r']) {
+        let escaped = path.replace('\\', "\\\\").replace('\n', "\\n").replace('\r', "\\r");
+        ("\\", escaped)
+    } else {
+        ("", path.to_string())
+    }
+}
+
+/// Fuzzy hashes are always printed in their native form, whatever the encoding.
+fn encode_digest(hasher: Hasher, encoding: DigestEncoding) -> String {
+    match encoding {
+        DigestEncoding::Base64 if !matches!(hasher, Hasher::Ssdeep(_)) => STANDARD.encode(hasher.finalize_bytes()),
+        _ => hasher.finalize(),
+    }
+}
+
+fn print_checksums(path: &str, hashers: Vec<Hasher>, encoding: DigestEncoding) {
+    let (prefix, path) = escape_path(path);
+    if hashers.len() == 1 {
+        for hasher in hashers {
+            println!("{}{}  {}", prefix, encode_digest(hasher, encoding), path);
+        }
+    } else {
+        for hasher in hashers {
+            println!("{}{} ({}) = {}", prefix, hasher.label(), path, encode_digest(hasher, encoding));
+        }
+    }
+}
+
+/// Accepts hex or base64, optionally prefixed the way webhook headers do (`sha256=...`).
+fn decode_signature(signature: &str) -> Option<Vec<u8>> {
+    let signature = signature.trim();
+    let signature = signature
+        .split_once('=')
+        .filter(|(scheme, _)| scheme.starts_with("sha"))
+        .map_or(signature, |(_, value)| value);
+
+    if signature.len().is_multiple_of(2) && signature.chars().all(|c| c.is_ascii_hexdigit()) {
+        return hex::decode(signature).ok();
+    }
+    STANDARD.decode(signature).or_else(|_| URL_SAFE_NO_PAD.decode(signature.trim_end_matches('='))).ok()
+}
+
+fn new_hashers(algorithms: &[HashAlgorithm], key: Option<&[u8]>) -> Vec<Hasher> {
+    match key {
+        Some(key) => algorithms
+            .iter()
+            .map(|algorithm| {
+                Hasher::keyed(*algorithm, key).unwrap_or_else(|| {
+                    eprintln!("Error: HMAC supports sha256 and sha512, not {}", algorithm.label());
+                    std::process::exit(1);
+                })
+            })
+            .collect(),
+        None => algorithms.iter().map(HashAlgorithm::hasher).collect(),
+    }
+}
+
+fn unescape_path(path: &str) -> String {
+    let mut result = String::with_capacity(path.len());
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+struct ChecksumEntry {
+    algorithm: HashAlgorithm,
+    expected: String,
+    path: String,
+}
+
+/// Parses `SHA256 (path) = HASH` (BSD tag) or `HASH  path` / `HASH *path` (GNU).
+fn parse_checksum_line(line: &str, forced: Option<HashAlgorithm>) -> Option<ChecksumEntry> {
+    let (escaped, line) = match line.strip_prefix('\\') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let decode = |path: &str| if escaped { unescape_path(path) } else { path.to_string() };
+    let is_hex = |digest: &str| !digest.is_empty() && digest.chars().all(|c| c.is_ascii_hexdigit());
+
+    if let (Some(open), Some(close)) = (line.find(" ("), line.rfind(") = ")) {
+        let digest = &line[close + 4..];
+        if let Some(algorithm) = HashAlgorithm::from_tag(&line[..open]) {
+            if open + 2 <= close && is_hex(digest) {
+                return Some(ChecksumEntry {
+                    algorithm,
+                    expected: digest.to_ascii_lowercase(),
+                    path: decode(&line[open + 2..close]),
+                });
+            }
+        }
+    }
+
+    let (digest, rest) = line.split_once(' ')?;
+    let path = rest.strip_prefix([' ', '*'])?;
+    if !is_hex(digest) || path.is_empty() {
+        return None;
+    }
+
+    let algorithm = match forced {
+        Some(algorithm) => algorithm,
+        None => HashAlgorithm::from_digest_len(digest.len())?,
+    };
+    Some(ChecksumEntry {
+        algorithm,
+        expected: digest.to_ascii_lowercase(),
+        path: decode(path),
+    })
+}
+
+fn read_checksum_file(path: &str) -> io::Result<Vec<String>> {
+    if path == "-" {
+        io::stdin().lock().lines().collect()
+    } else {
+        BufReader::new(File::open(path)?).lines().collect()
+    }
+}
+
+enum CheckOutcome {
+    Ok,
+    Failed,
+    Missing,
+    Unreadable(io::Error),
+}
+
+/// Verifies every listed file, printing OK/FAILED/MISSING per line. Returns true if all matched.
+fn run_check(args: &Args) -> bool {
+    let forced = match args.algorithm.as_slice() {
+        [] => None,
+        [algorithm] if *algorithm != HashAlgorithm::All => Some(*algorithm),
+        _ => {
+            eprintln!("Error: --check accepts at most one algorithm for untagged lines");
+            std::process::exit(1);
+        }
+    };
+    let mut entries = Vec::new();
+    let mut malformed = 0;
+    let mut unreadable = 0;
+
+    for list in &args.files {
+        let lines = match read_checksum_file(list) {
+            Ok(lines) => lines,
+            Err(e) => {
+                eprintln!("file-hasher: {}: {}", list, e);
+                unreadable += 1;
+                continue;
+            }
+        };
+
+        for line in lines {
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match parse_checksum_line(line, forced) {
+                Some(entry) => entries.push(entry),
+                None => malformed += 1,
+            }
+        }
+    }
+
+    let outcomes: Vec<_> = entries
+        .par_iter()
+        .map(|entry| match hash_path(&entry.path, &[entry.algorithm], false) {
+            Ok((_, hashers)) => {
+                let matches = hashers.into_iter().all(|hasher| hasher.finalize() == entry.expected);
+                if matches { CheckOutcome::Ok } else { CheckOutcome::Failed }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => CheckOutcome::Missing,
+            Err(e) => CheckOutcome::Unreadable(e),
+        })
+        .collect();
+
+    let (mut ok, mut failed, mut missing) = (0, 0, 0);
+    for (entry, outcome) in entries.iter().zip(outcomes) {
+        match outcome {
+            CheckOutcome::Ok => {
+                ok += 1;
+                if !args.quiet {
+                    println!("{}: {}", entry.path, "OK".green());
+                }
+            }
+            CheckOutcome::Failed => {
+                failed += 1;
+                println!("{}: {}", entry.path, "FAILED".red().bold());
+            }
+            CheckOutcome::Missing => {
+                missing += 1;
+                println!("{}: {}", entry.path, "MISSING".yellow().bold());
+            }
+            CheckOutcome::Unreadable(e) => {
+                failed += 1;
+                println!("{}: {} ({})", entry.path, "FAILED open or read".red().bold(), e);
+            }
+        }
+    }
+
+    eprintln!("---");
+    eprintln!("{} OK, {} FAILED, {} MISSING", ok, failed, missing);
+    if malformed > 0 {
+        eprintln!("{} {} improperly formatted", malformed, if malformed == 1 { "line is" } else { "lines are" });
+    }
+
+    if entries.is_empty() {
+        eprintln!("file-hasher: no properly formatted checksum lines found");
+    }
+    failed == 0 && missing == 0 && unreadable == 0 && !entries.is_empty()
+}
+
+/// Bytes read from each end of a file for the cheap second-pass comparison.
+const PARTIAL_BLOCK: u64 = 4096;
+
+#[derive(Serialize)]
+struct DuplicateSet {
+    size: u64,
+    hash: String,
+    wasted: u64,
+    paths: Vec<String>,
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Hashes the first and last block of a file, enough to split most same-size groups cheaply.
+fn partial_hash(path: &str, size: u64) -> io::Result<u64> {
+    let mut file = File::open(path)?;
+    let mut hasher = Xxh3::new();
+    let mut buffer = vec![0u8; PARTIAL_BLOCK.min(size) as usize];
+
+    file.read_exact(&mut buffer)?;
+    hasher.update(&buffer);
+    if size > PARTIAL_BLOCK {
+        let tail = PARTIAL_BLOCK.min(size - PARTIAL_BLOCK);
+        buffer.truncate(tail as usize);
+        file.seek(SeekFrom::Start(size - tail))?;
+        file.read_exact(&mut buffer)?;
+        hasher.update(&buffer);
+    }
+    Ok(hasher.digest())
+}
+
+/// Splits every group by `key`, dropping files that fail and groups left with a single member.
+fn refine<K, F>(groups: Vec<Vec<String>>, errors: &AtomicUsize, key: F) -> Vec<(K, Vec<String>)>
+where
+    K: Ord + Send,
+    F: Fn(&str) -> io::Result<K> + Sync,
+{
+    groups
+        .into_par_iter()
+        .flat_map_iter(|group| {
+            let mut split: BTreeMap<K, Vec<String>> = BTreeMap::new();
+            for path in group {
+                match key(&path) {
+                    Ok(key) => split.entry(key).or_default().push(path),
+                    Err(e) => {
+                        eprintln!("file-hasher: {}: {}", path, e);
+                        errors.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+            }
+            split.into_iter().filter(|(_, paths)| paths.len() > 1)
+        })
+        .collect()
+}
+
+fn find_duplicates(args: &DupesArgs, errors: &mut usize) -> Vec<DuplicateSet> {
+    let files = collect_files(&args.dirs, &args.walk, errors);
+
+    let mut by_size: BTreeMap<u64, Vec<String>> = BTreeMap::new();
+    let mut seen = HashSet::new();
+    for path in files {
+        let metadata = match fs::metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                eprintln!("file-hasher: {}: {}", path, e);
+                *errors += 1;
+                continue;
+            }
+        };
+        // Hard links to one inode already share storage, so only the first counts.
+        if file_id(&metadata).is_some_and(|id| !seen.insert(id)) {
+            continue;
+        }
+        if metadata.len() >= args.min_size {
+            by_size.entry(metadata.len()).or_default().push(path);
+        }
+    }
+
+    let failures = AtomicUsize::new(0);
+    let candidates: Vec<(u64, Vec<String>)> = by_size.into_iter().filter(|(_, paths)| paths.len() > 1).collect();
+    let sizes: HashMap<String, u64> = candidates
+        .iter()
+        .flat_map(|(size, paths)| paths.iter().map(move |path| (path.clone(), *size)))
+        .collect();
+
+    let groups = candidates.into_iter().map(|(_, paths)| paths).collect();
+    let groups = refine(groups, &failures, |path| Ok((sizes[path], partial_hash(path, sizes[path])?)));
+    let groups = refine(groups.into_iter().map(|(_, paths)| paths).collect(), &failures, |path| {
+        let (_, hash) = file_digest(path, args.algorithm)?;
+        Ok((sizes[path], hash))
+    });
+    *errors += failures.into_inner();
+
+    let mut sets: Vec<DuplicateSet> = groups
+        .into_iter()
+        .map(|((size, hash), mut paths)| {
+            paths.sort();
+            DuplicateSet {
+                size,
+                hash,
+                wasted: size * (paths.len() as u64 - 1),
+                paths,
+            }
+        })
+        .collect();
+    sets.sort_by(|a, b| b.wasted.cmp(&a.wasted).then_with(|| a.paths.cmp(&b.paths)));
+    sets
+}
+
+fn shell_quote(path: &str) -> String {
+    format!("'{}'", path.replace('\'', "'\\''"))
+}
+
+fn print_duplicates(sets: &[DuplicateSet], args: &DupesArgs) {
+    let total: u64 = sets.iter().map(|set| set.wasted).sum();
+
+    match args.format {
+        DupesFormat::Text => {
+            for set in sets {
+                println!(
+                    "{} {} x {} bytes, {} wasted",
+                    set.hash.bright_black(),
+                    set.paths.len(),
+                    set.size,
+                    format_bytes(set.wasted as f64).yellow()
+                );
+                for path in &set.paths {
+                    println!("  {}", path);
+                }
+            }
+            println!("---");
+            println!(
+                "{} duplicate sets, {} wasted",
+                sets.len(),
+                format_bytes(total as f64).yellow().bold()
+            );
+        }
+        DupesFormat::Json => {
+            let report = json!({ "sets": sets, "wasted": total });
+            println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default());
+        }
+        DupesFormat::Script => {
+            println!("#!/bin/sh");
+            println!("# {} duplicate sets, {} bytes reclaimable", sets.len(), total);
+            println!("set -e");
+            for set in sets {
+                let keep = shell_quote(&set.paths[0]);
+                println!();
+                println!("# keep {}", keep);
+                for path in &set.paths[1..] {
+                    match args.action {
+                        DupesAction::Hardlink => println!("ln -f -- {} {}", keep, shell_quote(path)),
+                        DupesAction::Delete => println!("rm -f -- {}", shell_quote(path)),
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn run_dupes(args: &DupesArgs) -> bool {
+    if args.algorithm == HashAlgorithm::All {
+        eprintln!("Error: dupes compares with a single algorithm");
+        std::process::exit(1);
+    }
+    if let Some(jobs) = args.jobs {
+        if let Err(e) = rayon::ThreadPoolBuilder::new().num_threads(jobs).build_global() {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+
+    let mut errors = 0;
+    let sets = find_duplicates(args, &mut errors);
+    print_duplicates(&sets, args);
+    errors == 0
+}
+
+const MANIFEST_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct ManifestEntry {
+    path: String,
+    size: u64,
+    mtime: i64,
+    mode: u32,
+    hash: String,
+}
+
+/// Everything covered by the signature; field order is fixed so serialization is canonical.
+#[derive(Serialize, Deserialize)]
+struct ManifestBody {
+    version: u32,
+    root: String,
+    created: String,
+    algorithm: String,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    symlinks: SymlinkPolicy,
+    files: Vec<ManifestEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    #[serde(flatten)]
+    body: ManifestBody,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    signature: Option<String>,
+}
+
+fn manifest_mac(body: &ManifestBody, key: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(&serde_json::to_vec(body).expect("manifest serializes"));
+    mac
+}
+
+#[cfg(unix)]
+fn file_mode(metadata: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o7777
+}
+
+#[cfg(not(unix))]
+fn file_mode(metadata: &fs::Metadata) -> u32 {
+    if metadata.permissions().readonly() { 0o444 } else { 0o644 }
+}
+
+fn file_mtime(metadata: &fs::Metadata) -> i64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_secs() as i64)
+}
+
+fn relative_path(root: &Path, path: &str) -> String {
+    let relative = Path::new(path).strip_prefix(root).unwrap_or(Path::new(path));
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn manifest_entry(root: &Path, path: &str, algorithm: HashAlgorithm) -> io::Result<ManifestEntry> {
+    let metadata = fs::metadata(path)?;
+    let (size, hash) = file_digest(path, algorithm)?;
+    Ok(ManifestEntry {
+        path: relative_path(root, path),
+        size,
+        mtime: file_mtime(&metadata),
+        mode: file_mode(&metadata),
+        hash,
+    })
+}
+
+fn create_manifest(args: &ManifestCreateArgs) -> bool {
+    if args.algorithm == HashAlgorithm::All {
+        eprintln!("Error: manifests record a single algorithm");
+        std::process::exit(1);
+    }
+    let root = Path::new(&args.dir);
+    if !root.is_dir() {
+        eprintln!("Error: Directory '{}' not found", args.dir);
+        std::process::exit(1);
+    }
+
+    let mut errors = 0;
+    let files = collect_files(std::slice::from_ref(&args.dir), &args.walk, &mut errors);
+    let results: Vec<_> = files
+        .par_iter()
+        .map(|path| (path, manifest_entry(root, path, args.algorithm)))
+        .collect();
+
+    let mut entries = Vec::new();
+    for (path, result) in results {
+        match result {
+            Ok(entry) => entries.push(entry),
+            Err(e) => {
+                eprintln!("file-hasher: {}: {}", path, e);
+                errors += 1;
+            }
+        }
+    }
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let body = ManifestBody {
+        version: MANIFEST_VERSION,
+        root: args.dir.clone(),
+        created: Utc::now().to_rfc3339(),
+        algorithm: args.algorithm.label().to_string(),
+        include: args.walk.include.clone(),
+        exclude: args.walk.exclude.clone(),
+        symlinks: args.walk.symlinks,
+        files: entries,
+    };
+    let signature = args
+        .key
+        .load()
+        .map(|key| hex::encode(manifest_mac(&body, &key).finalize().into_bytes()));
+    let signed = signature.is_some();
+    let manifest = Manifest { body, signature };
+
+    let json = serde_json::to_string_pretty(&manifest).expect("manifest serializes");
+    if let Err(e) = fs::write(&args.output, json + "\n") {
+        eprintln!("Error: Cannot write '{}': {}", args.output, e);
+        std::process::exit(1);
+    }
+
+    println!(
+        "Recorded {} files in {}{}",
+        manifest.body.files.len(),
+        args.output,
+        if signed { " (signed)" } else { "" }
+    );
+    errors == 0
+}
+
+fn verify_signature(manifest: &Manifest, key: Option<Vec<u8>>) {
+    match (&manifest.signature, key) {
+        (Some(signature), Some(key)) => {
+            let valid = hex::decode(signature)
+                .is_ok_and(|signature| manifest_mac(&manifest.body, &key).verify_slice(&signature).is_ok());
+            if !valid {
+                eprintln!("{}", "Manifest signature is invalid: it was modified or signed with another key".red().bold());
+                std::process::exit(1);
+            }
+            println!("{}", "Manifest signature OK".green());
+        }
+        (Some(_), None) => {
+            eprintln!("Error: Manifest is signed; pass --hmac-key or --hmac-key-file to verify it");
+            std::process::exit(1);
+        }
+        (None, Some(_)) => {
+            eprintln!("{}", "Manifest is not signed but a key was given; refusing to trust it".red().bold());
+            std::process::exit(1);
+        }
+        (None, None) => {}
+    }
+}
+
+fn verify_manifest(args: &ManifestVerifyArgs) -> bool {
+    let manifest: Manifest = match fs::read_to_string(&args.manifest)
+        .map_err(|e| e.to_string())
+        .and_then(|text| serde_json::from_str(&text).map_err(|e| e.to_string()))
+    {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            eprintln!("Error: Cannot read manifest '{}': {}", args.manifest, e);
+            std::process::exit(1);
+        }
+    };
+    if manifest.body.version != MANIFEST_VERSION {
+        eprintln!("Error: Unsupported manifest version {}", manifest.body.version);
+        std::process::exit(1);
+    }
+    verify_signature(&manifest, args.key.load());
+
+    let Some(algorithm) = HashAlgorithm::from_tag(&manifest.body.algorithm) else {
+        eprintln!("Error: Unknown algorithm '{}' in manifest", manifest.body.algorithm);
+        std::process::exit(1);
+    };
+    let dir = args.dir.clone().unwrap_or_else(|| manifest.body.root.clone());
+    let root = Path::new(&dir);
+    if !root.is_dir() {
+        eprintln!("Error: Directory '{}' not found", dir);
+        std::process::exit(1);
+    }
+
+    let walk = WalkArgs {
+        include: manifest.body.include.clone(),
+        exclude: manifest.body.exclude.clone(),
+        symlinks: manifest.body.symlinks,
+    };
+    let mut errors = 0;
+    let current: BTreeMap<String, String> = collect_files(std::slice::from_ref(&dir), &walk, &mut errors)
+        .into_iter()
+        .map(|path| (relative_path(root, &path), path))
+        .collect();
+    let recorded: BTreeMap<&str, &ManifestEntry> =
+        manifest.body.files.iter().map(|entry| (entry.path.as_str(), entry)).collect();
+
+    let changes: Vec<(String, String)> = manifest
+        .body
+        .files
+        .par_iter()
+        .filter_map(|expected| {
+            let Some(path) = current.get(&expected.path) else {
+                return Some((expected.path.clone(), "removed".red().to_string()));
+            };
+            let metadata = match fs::metadata(path) {
+                Ok(metadata) => metadata,
+                Err(e) => return Some((expected.path.clone(), format!("{} ({})", "unreadable".red(), e))),
+            };
+
+            if metadata.len() != expected.size {
+                return Some((expected.path.clone(), "modified".red().bold().to_string()));
+            }
+            match file_digest(path, algorithm) {
+                Ok((_, hash)) if hash != expected.hash => {
+                    return Some((expected.path.clone(), "modified".red().bold().to_string()));
+                }
+                Ok(_) => {}
+                Err(e) => return Some((expected.path.clone(), format!("{} ({})", "unreadable".red(), e))),
+            }
+
+            let mut details = Vec::new();
+            let mode = file_mode(&metadata);
+            if mode != expected.mode {
+                details.push(format!("mode {:o} -> {:o}", expected.mode, mode));
+            }
+            let mtime = file_mtime(&metadata);
+            if mtime != expected.mtime {
+                details.push(format!("mtime {} -> {}", expected.mtime, mtime));
+            }
+            (!details.is_empty())
+                .then(|| (expected.path.clone(), format!("{} ({})", "metadata".yellow(), details.join(", "))))
+        })
+        .collect();
+
+    let added: Vec<(String, String)> = current
+        .keys()
+        .filter(|path| !recorded.contains_key(path.as_str()))
+        .map(|path| (path.clone(), "added".yellow().bold().to_string()))
+        .collect();
+
+    let mut report: Vec<_> = changes.into_iter().chain(added).collect();
+    report.sort();
+    for (path, change) in &report {
+        println!("{}: {}", path, change);
+    }
+
+    println!("---");
+    if report.is_empty() {
+        println!("{} files match {}", manifest.body.files.len(), args.manifest.green());
+    } else {
+        println!(
+            "{} changes against {} recorded files",
+            report.len().to_string().red().bold(),
+            manifest.body.files.len()
+        );
+    }
+    report.is_empty() && errors == 0
+}
+
+const MERKLE_VERSION: u32 = 1;
+
+fn parse_chunk_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (digits, unit) = value.split_at(split);
+    let multiplier: u64 = match unit.to_ascii_uppercase().trim_end_matches("IB").trim_end_matches('B') {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        _ => return Err(format!("unknown size unit '{}'", unit)),
+    };
+
+    let size = digits
+        .parse::<u64>()
+        .map_err(|_| format!("invalid size '{}'", value))?
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("size '{}' is too large", value))?;
+    if size == 0 {
+        return Err("chunk size must be greater than zero".to_string());
+    }
+    Ok(size)
+}
+
+#[derive(Serialize, Deserialize)]
+struct MerkleTree {
+    version: u32,
+    file: String,
+    size: u64,
+    chunk_size: u64,
+    algorithm: String,
+    root: String,
+    chunks: Vec<String>,
+}
+
+fn digest_parts(algorithm: HashAlgorithm, parts: &[&[u8]]) -> Vec<u8> {
+    let mut hasher = algorithm.hasher();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize_bytes()
+}
+
+/// Leaves and inner nodes are domain-separated as in RFC 6962; an odd node is promoted unchanged.
+fn merkle_root(algorithm: HashAlgorithm, leaves: &[Vec<u8>]) -> Vec<u8> {
+    if leaves.is_empty() {
+        return digest_parts(algorithm, &[&[0]]);
+    }
+
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => digest_parts(algorithm, &[&[1], left, right]),
+                [single] => single.clone(),
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    level.remove(0)
+}
+
+/// Reads up to `buffer.len()` bytes, stopping early only at end of file.
+fn read_chunk(reader: &mut impl Read, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Hashes each chunk of the file as a leaf, returning the leaves and the total size.
+fn chunk_leaves(path: &str, chunk_size: u64, algorithm: HashAlgorithm) -> io::Result<(Vec<Vec<u8>>, u64)> {
+    let file = File::open(path)?;
+    let length = file.metadata()?.len();
+    let show_progress = io::stderr().is_terminal() && length >= PROGRESS_THRESHOLD;
+    let mut progress = show_progress.then(|| Progress::new(Some(length)));
+
+    let mut reader = BufReader::with_capacity(BUFFER_SIZE, file);
+    let mut buffer = vec![0u8; chunk_size.min(length.max(1)) as usize];
+    let mut leaves = Vec::new();
+    let mut total = 0u64;
+
+    loop {
+        let read = read_chunk(&mut reader, &mut buffer)?;
+        if read == 0 && !leaves.is_empty() {
+            break;
+        }
+        leaves.push(digest_parts(algorithm, &[&[0], &buffer[..read]]));
+        total += read as u64;
+        if let Some(progress) = progress.as_mut() {
+            progress.update(total);
+        }
+        if read < buffer.len() {
+            break;
+        }
+    }
+
+    if let Some(progress) = progress {
+        progress.finish(total);
+    }
+    Ok((leaves, total))
+}
+
+fn sidecar_path(file: &str, explicit: &Option<String>) -> String {
+    explicit.clone().unwrap_or_else(|| format!("{}.merkle", file))
+}
+
+fn create_merkle(args: &MerkleCreateArgs) -> bool {
+    if args.algorithm == HashAlgorithm::All {
+        eprintln!("Error: Merkle trees use a single algorithm");
+        std::process::exit(1);
+    }
+
+    let (leaves, size) = match chunk_leaves(&args.file, args.chunk_size, args.algorithm) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("Error: Cannot read '{}': {}", args.file, e);
+            std::process::exit(1);
+        }
+    };
+    let tree = MerkleTree {
+        version: MERKLE_VERSION,
+        file: Path::new(&args.file)
+            .file_name()
+            .map_or_else(|| args.file.clone(), |name| name.to_string_lossy().into_owned()),
+        size,
+        chunk_size: args.chunk_size,
+        algorithm: args.algorithm.label().to_string(),
+        root: hex::encode(merkle_root(args.algorithm, &leaves)),
+        chunks: leaves.iter().map(hex::encode).collect(),
+    };
+
+    let json = serde_json::to_string_pretty(&tree).expect("tree serializes") + "\n";
+    let output = sidecar_path(&args.file, &args.output);
+    if output == "-" {
+        print!("{}", json);
+        return true;
+    }
+    if let Err(e) = fs::write(&output, json) {
+        eprintln!("Error: Cannot write '{}': {}", output, e);
+        std::process::exit(1);
+    }
+
+    println!("Root:   {}", tree.root);
+    println!("Chunks: {} x {} bytes", tree.chunks.len(), tree.chunk_size);
+    println!("Tree:   {}", output);
+    true
+}
+
+#[derive(Serialize)]
+struct BadChunk {
+    index: usize,
+    offset: u64,
+    length: u64,
+    status: &'static str,
+}
+
+fn verify_merkle(args: &MerkleVerifyArgs) -> bool {
+    let path = sidecar_path(&args.file, &args.tree);
+    let tree: MerkleTree = match fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| serde_json::from_str(&text).map_err(|e| e.to_string()))
+    {
+        Ok(tree) => tree,
+        Err(e) => {
+            eprintln!("Error: Cannot read tree '{}': {}", path, e);
+            std::process::exit(1);
+        }
+    };
+    let Some(algorithm) = HashAlgorithm::from_tag(&tree.algorithm) else {
+        eprintln!("Error: Unknown algorithm '{}' in tree", tree.algorithm);
+        std::process::exit(1);
+    };
+    if tree.version != MERKLE_VERSION || tree.chunk_size == 0 {
+        eprintln!("Error: Unsupported tree file '{}'", path);
+        std::process::exit(1);
+    }
+
+    // The chunk list must hash to the recorded root, and to the trusted one if given.
+    let expected: Vec<Vec<u8>> = tree.chunks.iter().filter_map(|chunk| hex::decode(chunk).ok()).collect();
+    let computed_root = hex::encode(merkle_root(algorithm, &expected));
+    let tree_ok = expected.len() == tree.chunks.len()
+        && computed_root == tree.root
+        && args.root.as_ref().is_none_or(|root| root.eq_ignore_ascii_case(&computed_root));
+    if !tree_ok {
+        eprintln!("{}", "Tree file does not match its root hash; it can't be trusted".red().bold());
+        std::process::exit(1);
+    }
+
+    let (actual, size) = match chunk_leaves(&args.file, tree.chunk_size, algorithm) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("Error: Cannot read '{}': {}", args.file, e);
+            std::process::exit(1);
+        }
+    };
+
+    let chunk_length = |index: usize| tree.chunk_size.min(tree.size.saturating_sub(index as u64 * tree.chunk_size));
+    let mut bad = Vec::new();
+    for index in 0..expected.len().max(actual.len()) {
+        let status = match (expected.get(index), actual.get(index)) {
+            (Some(want), Some(got)) if want == got => continue,
+            (Some(_), Some(_)) if size < tree.size && (index as u64 + 1) * tree.chunk_size > size => "truncated",
+            (Some(_), Some(_)) => "corrupt",
+            (Some(_), None) => "missing",
+            (None, _) => "extra",
+        };
+        let length = match status {
+            "extra" => size.saturating_sub(index as u64 * tree.chunk_size).min(tree.chunk_size),
+            _ => chunk_length(index),
+        };
+        bad.push(BadChunk {
+            index,
+            offset: index as u64 * tree.chunk_size,
+            length,
+            status,
+        });
+    }
+
+    let ok = bad.is_empty();
+    if args.json {
+        let report = json!({
+            "file": args.file,
+            "root": tree.root,
+            "size": size,
+            "expected_size": tree.size,
+            "chunks": expected.len(),
+            "ok": ok,
+            "bad": bad,
+        });
+        println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default());
+        return ok;
+    }
+
+    for chunk in &bad {
+        let status = match chunk.status {
+            "corrupt" => chunk.status.red().bold(),
+            _ => chunk.status.yellow().bold(),
+        };
+        println!(
+            "chunk {:>6}  bytes {}-{}  {}",
+            chunk.index,
+            chunk.offset,
+            chunk.offset + chunk.length.max(1) - 1,
+            status
+        );
+    }
+    println!("---");
+    if ok {
+        println!("{} all {} chunks match root {}", "OK".green().bold(), expected.len(), tree.root);
+    } else {
+        println!("{} of {} chunks bad", bad.len().to_string().red().bold(), expected.len());
+    }
+    ok
+}
+
+struct FuzzySample {
+    hash: String,
+    name: String,
+}
+
+/// Reads known signatures from ssdeep's own list format or from this tool's `HASH  path` output.
+fn load_known_hashes(path: &str) -> Vec<FuzzySample> {
+    let lines = read_checksum_file(path).unwrap_or_else(|e| {
+        eprintln!("Error: Cannot read '{}': {}", path, e);
+        std::process::exit(1);
+    });
+
+    lines
+        .iter()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with("ssdeep,") && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (hash, name) = match line.split_once(",\"") {
+                Some((hash, name)) => (hash, name.trim_end_matches('"')),
+                None => line.split_once("  ").unwrap_or((line, line)),
+            };
+            parse_fuzzy(hash).map(|_| FuzzySample {
+                hash: hash.to_string(),
+                name: name.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn run_compare(args: &CompareArgs) -> bool {
+    let mut errors = 0;
+    let mut signatures = Vec::new();
+    let mut paths = Vec::new();
+    for input in &args.inputs {
+        if Path::new(input).exists() {
+            paths.push(input.clone());
+        } else if parse_fuzzy(input).is_some() {
+            signatures.push(input.clone());
+        } else if input.contains(':') {
+            eprintln!("file-hasher: {}: invalid ssdeep signature", input);
+            errors += 1;
+        } else {
+            paths.push(input.clone());
+        }
+    }
+    let paths = collect_files(&paths, &args.walk, &mut errors);
+
+    let hashed: Vec<_> = paths
+        .par_iter()
+        .map(|path| (path, file_digest(path, HashAlgorithm::Ssdeep)))
+        .collect();
+    let mut samples: Vec<FuzzySample> = signatures
+        .into_iter()
+        .map(|sig| FuzzySample { hash: sig.clone(), name: sig })
+        .collect();
+    for (path, result) in hashed {
+        match result {
+            Ok((_, hash)) => samples.push(FuzzySample { hash, name: path.clone() }),
+            Err(e) => {
+                eprintln!("file-hasher: {}: {}", path, e);
+                errors += 1;
+            }
+        }
+    }
+
+    let known = args.known.as_deref().map(load_known_hashes);
+    let pairs: Vec<(&FuzzySample, &FuzzySample)> = match &known {
+        Some(known) => samples
+            .iter()
+            .flat_map(|sample| known.iter().map(move |known| (sample, known)))
+            .collect(),
+        None => samples
+            .iter()
+            .enumerate()
+            .flat_map(|(i, a)| samples[i + 1..].iter().map(move |b| (a, b)))
+            .collect(),
+    };
+
+    let mut matches: Vec<(u32, &str, &str)> = pairs
+        .par_iter()
+        .filter_map(|(a, b)| {
+            let score = fuzzy_compare(&a.hash, &b.hash)?;
+            (score >= args.threshold).then_some((score, a.name.as_str(), b.name.as_str()))
+        })
+        .collect();
+    matches.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| (a.1, a.2).cmp(&(b.1, b.2))));
+
+    for (score, a, b) in &matches {
+        let score = match score {
+            80.. => score.to_string().green().bold(),
+            40..=79 => score.to_string().yellow(),
+            _ => score.to_string().normal(),
+        };
+        println!("{:>3}  {}  {}", score, a, b);
+    }
+    if matches.is_empty() {
+        println!("No pairs scored {} or higher", args.threshold);
+    }
+    errors == 0
+}
+
+fn main() -> io::Result<()> {
+    let args = Args::parse();
+    let show_progress = !args.no_progress && io::stderr().is_terminal();
+
+    match &args.command {
+        Some(Command::Dupes(dupes)) => {
+            if !run_dupes(dupes) {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Command::Manifest { action }) => {
+            let ok = match action {
+                ManifestCommand::Create(create) => create_manifest(create),
+                ManifestCommand::Verify(verify) => verify_manifest(verify),
+            };
+            if !ok {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Command::Compare(compare)) => {
+            if !run_compare(compare) {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Command::Merkle { action }) => {
+            let ok = match action {
+                MerkleCommand::Create(create) => create_merkle(create),
+                MerkleCommand::Verify(verify) => verify_merkle(verify),
+            };
+            if !ok {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        None => {}
+    }
+
+    if let Some(jobs) = args.jobs {
+        rayon::ThreadPoolBuilder::new().num_threads(jobs).build_global().map_err(io::Error::other)?;
+    }
+
+    if args.check {
+        if !run_check(&args) {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let key = args.key.load();
+    let algorithms = match (&key, args.algorithm.is_empty()) {
+        (Some(_), true) => vec![HashAlgorithm::Sha256],
+        _ => HashAlgorithm::resolve(&args.algorithm),
+    };
+
+    if let Some(expected) = &args.verify {
+        let [file] = args.files.as_slice() else {
+            eprintln!("Error: --verify takes exactly one file");
+            std::process::exit(1);
+        };
+        let [algorithm] = algorithms.as_slice() else {
+            eprintln!("Error: --verify needs exactly one algorithm");
+            std::process::exit(1);
+        };
+        let Some(expected) = decode_signature(expected) else {
+            eprintln!("Error: Expected digest is neither hex nor base64");
+            std::process::exit(1);
+        };
+
+        let hashers = new_hashers(std::slice::from_ref(algorithm), key.as_deref());
+        let (_, hashers) = hash_with(file, hashers, show_progress)?;
+        let label = hashers[0].label();
+        if hashers.into_iter().all(|hasher| hasher.verify(&expected)) {
+            println!("{}: {} {}", file, label, "OK".green());
+            return Ok(());
+        }
+        println!("{}: {} {}", file, label, "FAILED".red().bold());
+        std::process::exit(1);
+    }
+
+    // The labelled summary is only for a person looking at a single file with the default
+    // algorithms; anything else prints checksum lines that `-c` can read back.
+    let summary = args.algorithm.is_empty() && io::stdout().is_terminal();
+    if let [file] = args.files.as_slice() {
+        if summary && !Path::new(file).is_dir() {
+            if file != "-" && !Path::new(file).exists() {
+                eprintln!("Error: File '{}' not found", file);
+                std::process::exit(1);
+            }
+
+            let hashers = new_hashers(&algorithms, key.as_deref());
+            let (file_size, hashers) = hash_with(file, hashers, show_progress)?;
+            println!("File: {}", file);
+            println!("Size: {} bytes", file_size);
+            println!("---");
+
+            let width = hashers.iter().map(|hasher| hasher.label().len() + 1).max().unwrap_or(0);
+            for hasher in hashers {
+                let label = format!("{}:", hasher.label());
+                println!("{:<width$} {}", label, encode_digest(hasher, args.encoding), width = width);
+            }
+            return Ok(());
+        }
+    }
+
+    let mut errors = 0;
+    let files = collect_files(&args.files, &args.walk, &mut errors);
+
+    let results: Vec<_> = files
+        .par_iter()
+        .map(|file| (file, hash_with(file, new_hashers(&algorithms, key.as_deref()), false)))
+        .collect();
+
+    for (file, result) in results {
+        match result {
+            Ok((_, hashers)) => print_checksums(file, hashers, args.encoding),
+            Err(e) => {
+                eprintln!("file-hasher: {}: {}", file, e);
+                errors += 1;
+            }
+        }
+    }
+
+    if errors > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ssdeep(data: &[u8]) -> String {
+        let mut hasher = FuzzyHasher::new();
+        hasher.update(data);
+        hasher.digest()
+    }
+
+    /// Deterministic text-like input long enough to push the block size well past the minimum.
+    fn generated(len: usize, mut seed: u32) -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345) & 0x7fff_ffff;
+                b"abcdefghij \n"[((seed >> 16) % 12) as usize]
+            })
+            .collect()
+    }
+
+    // Vectors from the python-ssdeep (libfuzzy binding) documentation.
+    const CTPH_LOWER: &str = "3:AXGBicFlgVNhBGcL6wCrFQEv:AXGHsNhxLsr2C";
+    const CTPH_UPPER: &str = "3:AXGBicFlIHBGcL6wCrFQEv:AXGH6xLsr2C";
+
+    #[test]
+    fn ssdeep_matches_libfuzzy_vectors() {
+        assert_eq!(ssdeep(b""), "3::");
+        assert_eq!(ssdeep(b"Also called fuzzy hashes, Ctph can match inputs that have homologies."), CTPH_LOWER);
+        assert_eq!(ssdeep(b"Also called fuzzy hashes, CTPH can match inputs that have homologies."), CTPH_UPPER);
+    }
+
+    #[test]
+    fn ssdeep_matches_reference_spamsum_on_large_inputs() {
+        // Expected values come from an independent implementation of the original spamsum algorithm.
+        assert_eq!(
+            ssdeep(&generated(5_000, 7)),
+            "96:BxrCZ3TvUxx/F6F0Y2asUBq9SBOodyAuQe/PBrrTC1t4F7GN9iw2:3rCRUxx/cFVjB0SBOdrtC1tYGN9iw2"
+        );
+        assert_eq!(
+            ssdeep(&generated(100_000, 1)),
+            "1536:KQjNtBuqRiDvh9rhRBTY3/A9ME7B1VqH0tjvEyULgHGZrVBUNer8gShWVB0L6GLm:lfItRBT55KA2O6Ma"
+        );
+        assert_eq!(
+            ssdeep(&generated(100_000, 2)),
+            "1536:TNhqT13LNrBURMSSYOeg7idbUhSp6N4Byw4VojDV+I0HOn1FY+WC+jk/upe4:TNHw57f7Nyfkgh4"
+        );
+    }
+
+    #[test]
+    fn ssdeep_is_independent_of_read_boundaries() {
+        let data = generated(50_000, 3);
+        let mut hasher = FuzzyHasher::new();
+        for chunk in data.chunks(4093) {
+            hasher.update(chunk);
+        }
+        assert_eq!(hasher.digest(), ssdeep(&data));
+    }
+
+    #[test]
+    fn fuzzy_compare_matches_libfuzzy_scores() {
+        assert_eq!(fuzzy_compare(CTPH_LOWER, CTPH_UPPER), Some(22));
+        assert_eq!(fuzzy_compare(CTPH_UPPER, CTPH_LOWER), Some(22));
+        assert_eq!(fuzzy_compare(CTPH_LOWER, CTPH_LOWER), Some(100));
+
+        let a = ssdeep(&generated(100_000, 1));
+        let b = ssdeep(&generated(100_000, 2));
+        assert_eq!(fuzzy_compare(&a, &b), Some(0));
+    }
+
+    #[test]
+    fn fuzzy_compare_ignores_signatures_too_far_apart() {
+        assert_eq!(fuzzy_compare("3:AXGBicFlgVNhBGcL6wCrFQEv:x", "12:AXGBicFlgVNhBGcL6wCrFQEv:x"), Some(0));
+    }
+
+    #[test]
+    fn parse_fuzzy_rejects_impossible_block_sizes() {
+        assert!(parse_fuzzy("3:abc:def").is_some());
+        assert!(parse_fuzzy(&format!("{}:abc:def", 3u64 << 30)).is_some());
+        assert!(parse_fuzzy(&format!("{}:abc:def", 3u64 << 31)).is_none());
+        assert!(parse_fuzzy("5:abc:def").is_none());
+        assert!(parse_fuzzy("0:abc:def").is_none());
+        assert_eq!(fuzzy_compare("18446744073709551615:abc:def", "3:abc:def"), None);
+    }
+
+    #[test]
+    fn parse_fuzzy_strips_ssdeep_list_filenames() {
+        let (size, first, second) = parse_fuzzy("3:AAAAAAbc:def,\"some/file\"").unwrap();
+        assert_eq!(size, 3);
+        assert_eq!(first, b"AAAbc");
+        assert_eq!(second, b"def");
+    }
+
+    #[test]
+    fn all_leaves_out_ssdeep_unless_asked_for() {
+        assert!(!HashAlgorithm::resolve(&[HashAlgorithm::All]).contains(&HashAlgorithm::Ssdeep));
+        let algorithms = HashAlgorithm::resolve(&[HashAlgorithm::All, HashAlgorithm::Ssdeep]);
+        assert_eq!(algorithms.last(), Some(&HashAlgorithm::Ssdeep));
+    }
+
+    #[test]
+    fn base64_encoding_leaves_fuzzy_hashes_alone() {
+        let mut sha256 = HashAlgorithm::Sha256.hasher();
+        let mut fuzzy = HashAlgorithm::Ssdeep.hasher();
+        sha256.update(b"b");
+        fuzzy.update(b"b");
+        assert_eq!(encode_digest(sha256, DigestEncoding::Base64), "PiPoFgA5WUoziU9lZOGxNIu9egCI1CxKy3PurtWcAJ0=");
+        assert_eq!(encode_digest(fuzzy, DigestEncoding::Base64), "3:H:H");
+    }
+}
@@ -1,59 +1,3321 @@
-use std::env;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use base64::{engine::general_purpose, Engine};
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand};
+use colored::Colorize;
+use hdrhistogram::Histogram;
+use notify_rust::Notification;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use regex::Regex;
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, ACCEPT, AUTHORIZATION, CONNECTION, COOKIE, HOST, LOCATION, RETRY_AFTER,
+    SERVER, SET_COOKIE, USER_AGENT,
+};
+use reqwest::redirect::Policy;
+use reqwest::{Client, Method};
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fs::{self, File};
+use std::future::Future;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tokio::task::JoinSet;
+use tokio_native_tls::{native_tls, TlsConnector};
+use url::Url;
 
-async fn check_url(url: String) -> String {
-    let client = reqwest::Client::new();
-    match client.get(&url).send().await {
-        Ok(response) => format!("{} - {}", url, response.status().as_u16()),
-        Err(e) => format!("{} - Error: {}", url, e),
+/// Redirect hops followed before giving up, matching reqwest's default.
+const MAX_REDIRECTS: usize = 10;
+/// Upper bound for a single retry delay, including server-provided Retry-After values.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(300);
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Concurrent HTTP status checker and content discovery tool", long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[arg(required = true, help = "Base URL")]
+    base_url: Option<String>,
+
+    #[arg(required = true, help = "Wordlist with paths to check, one per line")]
+    paths_file: Option<String>,
+
+    #[command(flatten)]
+    request: RequestArgs,
+
+    #[command(flatten)]
+    client: ClientArgs,
+
+    #[command(flatten)]
+    retry: RetryArgs,
+
+    #[arg(short, long, value_delimiter = ',', help = "Also try each word with these extensions (e.g. php,bak,json)")]
+    extensions: Vec<String>,
+
+    #[arg(short, long, default_value = "0", help = "Recurse into discovered directories up to this depth")]
+    recursion_depth: usize,
+
+    #[arg(long, value_enum, default_value = "follow", help = "How to handle redirects")]
+    redirects: RedirectMode,
+
+    #[arg(long, visible_alias = "mc", value_delimiter = ',', value_parser = parse_code_range, help = "Show only these status codes (e.g. 200-299,301)")]
+    match_code: Vec<CodeRange>,
+
+    #[arg(long, visible_alias = "fc", value_delimiter = ',', value_parser = parse_code_range, help = "Hide these status codes")]
+    filter_code: Vec<CodeRange>,
+
+    #[arg(long, visible_alias = "ms", value_delimiter = ',', help = "Show only responses of these sizes in bytes")]
+    match_size: Vec<usize>,
+
+    #[arg(long, visible_alias = "fs", value_delimiter = ',', help = "Hide responses of these sizes in bytes")]
+    filter_size: Vec<usize>,
+
+    #[arg(long, visible_alias = "mw", value_delimiter = ',', help = "Show only responses with these word counts")]
+    match_words: Vec<usize>,
+
+    #[arg(long, visible_alias = "fw", value_delimiter = ',', help = "Hide responses with these word counts")]
+    filter_words: Vec<usize>,
+
+    #[arg(long, visible_alias = "mr", help = "Show only responses whose body matches this regex")]
+    match_regex: Option<Regex>,
+
+    #[arg(long, visible_alias = "fr", help = "Hide responses whose body matches this regex")]
+    filter_regex: Option<Regex>,
+
+    #[arg(long = "soft-404", value_enum, default_value = "flag", help = "What to do with responses that look like the server's not-found page")]
+    soft_404: Soft404Mode,
+
+    #[arg(short, long, help = "Show every redirect hop with a DNS/connect/TLS/TTFB timing breakdown (opens a fresh connection per request)")]
+    verbose: bool,
+
+    #[arg(short, long, value_parser = parse_interval, help = "Re-check the URL list every INTERVAL (e.g. 30s, 5m, 1h) and report up/down changes")]
+    watch: Option<Duration>,
+
+    #[arg(long, requires = "watch", help = "Show a desktop notification when a URL goes up or down")]
+    notify: bool,
+
+    #[arg(long, requires = "watch", help = "Shell command to run when a URL goes up or down")]
+    on_change: Option<String>,
+
+    #[arg(long, requires = "watch", help = "Append check results to this file and compute uptime from it")]
+    history: Option<String>,
+
+    #[arg(short, long, conflicts_with = "watch", help = "Export every request and response, including each redirect hop, to this file (opens a fresh connection per request)")]
+    output: Option<String>,
+
+    #[arg(long, value_enum, requires = "output", help = "Export format [default: har for .har files, ndjson otherwise]")]
+    format: Option<ExportFormat>,
+
+    #[arg(long, requires = "output", help = "Include request and response bodies in the export")]
+    include_bodies: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum ExportFormat {
+    /// HTTP Archive 1.2, loadable in browser devtools
+    Har,
+    /// One JSON object per line
+    Ndjson,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run a declarative health-check suite from a TOML or JSON file
+    Check(CheckArgs),
+    /// Crawl a site and report broken links
+    Crawl(CrawlArgs),
+    /// Load-test a URL and report throughput and latency percentiles
+    Bench(BenchArgs),
+}
+
+/// How each request is shaped, shared by the modes that send a single kind of request.
+#[derive(clap::Args, Debug)]
+struct RequestArgs {
+    #[arg(short = 'X', long, default_value = "GET", help = "HTTP method")]
+    method: String,
+
+    #[arg(short = 'H', long = "header", help = "Extra header as 'Name: value' (repeatable)")]
+    headers: Vec<String>,
+
+    #[arg(short, long, conflicts_with = "data_file", help = "Request body")]
+    data: Option<String>,
+
+    #[arg(long, help = "Read request body from file (use '-' for stdin)")]
+    data_file: Option<String>,
+
+    #[arg(short, long, conflicts_with = "bearer", help = "Basic auth as 'user:password'")]
+    user: Option<String>,
+
+    #[arg(long, help = "Bearer token for the Authorization header")]
+    bearer: Option<String>,
+
+    #[arg(short = 'b', long = "cookie", help = "Cookie as 'name=value' (repeatable)")]
+    cookies: Vec<String>,
+}
+
+/// Connection options shared by every mode.
+#[derive(clap::Args, Debug)]
+struct ClientArgs {
+    #[arg(short, long, default_value = "10", help = "Maximum concurrent requests")]
+    concurrency: usize,
+
+    #[arg(short, long, default_value = "10", help = "Request timeout in seconds")]
+    timeout: u64,
+
+    #[arg(short = 'A', long, default_value = concat!("http-status/", env!("CARGO_PKG_VERSION")), help = "User-Agent header")]
+    user_agent: String,
+}
+
+impl ClientArgs {
+    fn builder(&self) -> reqwest::ClientBuilder {
+        if self.concurrency == 0 {
+            fail("concurrency must be at least 1".to_string());
+        }
+
+        Client::builder()
+            .user_agent(&self.user_agent)
+            .timeout(Duration::from_secs(self.timeout))
+            .pool_max_idle_per_host(self.concurrency)
+    }
+
+    fn build_client(&self) -> Client {
+        self.builder()
+            .build()
+            .unwrap_or_else(|e| fail(format!("failed to build HTTP client: {}", e)))
+    }
+}
+
+/// Retry and rate limiting options, so scans don't trip WAFs on production hosts.
+#[derive(clap::Args, Debug)]
+struct RetryArgs {
+    #[arg(long, default_value = "0", help = "Retry connection errors and 429/503 responses up to N times")]
+    retries: u32,
+
+    #[arg(long, default_value = "500", help = "Initial retry delay in milliseconds, doubled per attempt with jitter")]
+    backoff: u64,
+
+    #[arg(long, help = "Global limit in requests per second")]
+    rate: Option<f64>,
+
+    #[arg(long, help = "Maximum concurrent requests per host")]
+    per_host: Option<usize>,
+}
+
+#[derive(clap::Args, Debug)]
+struct CheckArgs {
+    #[arg(help = "Suite file (.toml or .json)")]
+    suite: String,
+
+    #[arg(long, help = "Write a JUnit XML report to this file")]
+    junit: Option<String>,
+
+    #[command(flatten)]
+    client: ClientArgs,
+
+    #[command(flatten)]
+    retry: RetryArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct CrawlArgs {
+    #[arg(help = "URL to start crawling from")]
+    url: String,
+
+    #[arg(short = 'd', long, default_value = "5", help = "Maximum link depth from the start page")]
+    max_depth: usize,
+
+    #[arg(short = 'p', long, default_value = "500", help = "Maximum number of pages to crawl")]
+    max_pages: usize,
+
+    #[arg(long, help = "Also check links that leave the start URL's origin (they are never crawled)")]
+    external: bool,
+
+    #[arg(long, help = "Ignore robots.txt")]
+    ignore_robots: bool,
+
+    #[command(flatten)]
+    client: ClientArgs,
+
+    #[command(flatten)]
+    retry: RetryArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct BenchArgs {
+    #[arg(help = "URL to load-test")]
+    url: String,
+
+    #[arg(short = 'n', long, conflicts_with = "duration", help = "Total number of requests [default: 200]")]
+    requests: Option<u64>,
+
+    #[arg(short = 'z', long, value_parser = parse_interval, help = "Run for this long instead of a fixed count (e.g. 30s, 5m)")]
+    duration: Option<Duration>,
+
+    #[arg(long, help = "Send at a fixed rate of requests per second instead of as fast as possible")]
+    rate: Option<f64>,
+
+    #[command(flatten)]
+    request: RequestArgs,
+
+    #[command(flatten)]
+    client: ClientArgs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum Soft404Mode {
+    /// Skip baseline probing entirely
+    Off,
+    /// Report matching responses with a [soft-404] marker
+    Flag,
+    /// Suppress matching responses
+    Hide,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum RedirectMode {
+    /// Follow every redirect (up to 10 hops)
+    Follow,
+    /// Report redirects as-is without following them
+    Off,
+    /// Follow only redirects that stay on the same host
+    SameHost,
+}
+
+impl RedirectMode {
+    fn allows(&self, origin: &Url, next: &Url) -> bool {
+        match self {
+            RedirectMode::Follow => true,
+            RedirectMode::Off => false,
+            RedirectMode::SameHost => origin.host_str() == next.host_str(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CodeRange {
+    start: u16,
+    end: u16,
+}
+
+impl CodeRange {
+    fn contains(&self, code: u16) -> bool {
+        (self.start..=self.end).contains(&code)
+    }
+}
+
+fn parse_code_range(s: &str) -> Result<CodeRange, String> {
+    let parse = |v: &str| {
+        v.trim()
+            .parse::<u16>()
+            .map_err(|_| format!("invalid status code '{}'", v))
+    };
+
+    let (start, end) = match s.split_once('-') {
+        Some((start, end)) => (parse(start)?, parse(end)?),
+        None => {
+            let code = parse(s)?;
+            (code, code)
+        }
+    };
+
+    if start > end {
+        return Err(format!("invalid status code range '{}'", s));
+    }
+    Ok(CodeRange { start, end })
+}
+
+#[derive(Debug, Clone)]
+enum Auth {
+    Basic(String, Option<String>),
+    Bearer(String),
+}
+
+/// A failed request; connection-level failures are worth retrying.
+#[derive(Debug)]
+struct RequestError {
+    message: String,
+    retryable: bool,
+}
+
+impl RequestError {
+    fn retryable(message: String) -> Self {
+        RequestError { message, retryable: true }
+    }
+}
+
+impl From<String> for RequestError {
+    fn from(message: String) -> Self {
+        RequestError { message, retryable: false }
+    }
+}
+
+impl From<&str> for RequestError {
+    fn from(message: &str) -> Self {
+        RequestError::from(message.to_string())
+    }
+}
+
+impl From<reqwest::Error> for RequestError {
+    fn from(error: reqwest::Error) -> Self {
+        RequestError {
+            retryable: error.is_connect(),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Whether an attempt should be repeated, and after how long if the server said so.
+#[derive(Debug)]
+enum Attempt {
+    Done,
+    Retry(Option<Duration>),
+}
+
+/// Parses Retry-After as either delay-seconds or an HTTP date.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    (date.with_timezone(&Utc) - Utc::now()).to_std().ok()
+}
+
+/// Rate limited (429) and unavailable (503) responses are retried, honoring Retry-After.
+fn status_attempt(status: u16, retry_after: Option<&str>) -> Attempt {
+    if status == 429 || status == 503 {
+        Attempt::Retry(retry_after.and_then(parse_retry_after))
+    } else {
+        Attempt::Done
+    }
+}
+
+fn reqwest_attempt(result: &Result<reqwest::Response, reqwest::Error>) -> Attempt {
+    match result {
+        Ok(response) => status_attempt(
+            response.status().as_u16(),
+            response.headers().get(RETRY_AFTER).and_then(|v| v.to_str().ok()),
+        ),
+        Err(e) if e.is_connect() => Attempt::Retry(None),
+        Err(_) => Attempt::Done,
+    }
+}
+
+/// Retry policy, global request rate and per-host concurrency shared by every request of a run.
+#[derive(Debug, Default)]
+struct Throttle {
+    retries: u32,
+    backoff: Duration,
+    interval: Option<Duration>,
+    next_slot: tokio::sync::Mutex<Option<Instant>>,
+    per_host: Option<usize>,
+    hosts: Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+impl Throttle {
+    fn new(args: &RetryArgs) -> Self {
+        let interval = match args.rate {
+            Some(rate) if rate > 0.0 => Some(Duration::from_secs_f64(1.0 / rate)),
+            Some(_) => fail("rate must be greater than zero".to_string()),
+            None => None,
+        };
+        if args.per_host == Some(0) {
+            fail("per-host limit must be at least 1".to_string());
+        }
+
+        Throttle {
+            retries: args.retries,
+            backoff: Duration::from_millis(args.backoff),
+            interval,
+            per_host: args.per_host,
+            ..Default::default()
+        }
+    }
+
+    /// Waits for the next free slot of the global rate limit.
+    async fn wait_turn(&self) {
+        let Some(interval) = self.interval else {
+            return;
+        };
+
+        let slot = {
+            let mut next = self.next_slot.lock().await;
+            let slot = next.map_or_else(Instant::now, |next| next.max(Instant::now()));
+            *next = Some(slot + interval);
+            slot
+        };
+        tokio::time::sleep_until(slot.into()).await;
+    }
+
+    async fn host_permit(&self, url: &str) -> Option<OwnedSemaphorePermit> {
+        let limit = self.per_host?;
+        let host = Url::parse(url)
+            .ok()
+            .map(|u| format!("{}:{}", u.host_str().unwrap_or_default(), u.port_or_known_default().unwrap_or_default()))
+            .unwrap_or_default();
+
+        let semaphore = {
+            let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+            Arc::clone(hosts.entry(host).or_insert_with(|| Arc::new(Semaphore::new(limit))))
+        };
+        semaphore.acquire_owned().await.ok()
+    }
+
+    /// Exponential backoff with jitter in [delay/2, delay], unless the server asked for a delay.
+    fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        let delay = retry_after.unwrap_or_else(|| {
+            let base = self.backoff.saturating_mul(2u32.saturating_pow(attempt));
+            base.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+        });
+        delay.min(MAX_RETRY_DELAY)
+    }
+
+    /// Runs `send` under the rate and per-host limits, repeating it while `classify` asks for a retry.
+    async fn run<T, F, Fut>(&self, url: &str, mut send: F, classify: impl Fn(&T) -> Attempt) -> T
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let mut attempt = 0;
+        loop {
+            let result = {
+                let _permit = self.host_permit(url).await;
+                self.wait_turn().await;
+                send().await
+            };
+
+            match classify(&result) {
+                Attempt::Retry(retry_after) if attempt < self.retries => {
+                    tokio::time::sleep(self.delay(attempt, retry_after)).await;
+                    attempt += 1;
+                }
+                _ => return result,
+            }
+        }
+    }
+}
+
+/// Everything needed to issue a request, shared by all tasks.
+#[derive(Debug)]
+struct RequestTemplate {
+    client: Client,
+    method: Method,
+    body: Option<Vec<u8>>,
+    auth: Option<Auth>,
+    headers: HeaderMap,
+    user_agent: String,
+    redirects: RedirectMode,
+    timeout: Duration,
+    /// Follow redirects by hand so every hop is timed and kept, for verbose output and exports.
+    trace: bool,
+    throttle: Throttle,
+}
+
+impl RequestTemplate {
+    fn build(&self, url: &str) -> reqwest::RequestBuilder {
+        let mut request = self.client.request(self.method.clone(), url);
+
+        if let Some(body) = &self.body {
+            request = request.body(body.clone());
+        }
+
+        match &self.auth {
+            Some(Auth::Basic(user, pass)) => request.basic_auth(user, pass.as_ref()),
+            Some(Auth::Bearer(token)) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// Headers sent with every request, as recorded in exports and used by verbose mode.
+    fn request_headers(&self, url: &Url, with_auth: bool) -> Vec<(String, String)> {
+        let host = url.host_str().unwrap_or_default();
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.to_string(),
+        };
+
+        let mut headers = vec![
+            (HOST.to_string(), host),
+            (USER_AGENT.to_string(), self.user_agent.clone()),
+            (ACCEPT.to_string(), "*/*".to_string()),
+        ];
+        for (name, value) in &self.headers {
+            headers.push((name.to_string(), String::from_utf8_lossy(value.as_bytes()).into_owned()));
+        }
+        if let Some(authorization) = self.authorization().filter(|_| with_auth) {
+            headers.push((AUTHORIZATION.to_string(), authorization));
+        }
+        headers
+    }
+
+    fn authorization(&self) -> Option<String> {
+        match &self.auth {
+            Some(Auth::Basic(user, pass)) => {
+                let credentials = format!("{}:{}", user, pass.as_deref().unwrap_or(""));
+                Some(format!("Basic {}", general_purpose::STANDARD.encode(credentials)))
+            }
+            Some(Auth::Bearer(token)) => Some(format!("Bearer {}", token)),
+            None => None,
+        }
+    }
+}
+
+/// Decides which responses are reported, in the spirit of ffuf's matchers and filters.
+/// Every matcher that is set must accept a response and no filter may reject it.
+#[derive(Debug)]
+struct Filters {
+    match_code: Vec<CodeRange>,
+    filter_code: Vec<CodeRange>,
+    match_size: Vec<usize>,
+    filter_size: Vec<usize>,
+    match_words: Vec<usize>,
+    filter_words: Vec<usize>,
+    match_regex: Option<Regex>,
+    filter_regex: Option<Regex>,
+}
+
+impl Filters {
+    fn from_args(args: &Args) -> Self {
+        Filters {
+            match_code: args.match_code.clone(),
+            filter_code: args.filter_code.clone(),
+            match_size: args.match_size.clone(),
+            filter_size: args.filter_size.clone(),
+            match_words: args.match_words.clone(),
+            filter_words: args.filter_words.clone(),
+            match_regex: args.match_regex.clone(),
+            filter_regex: args.filter_regex.clone(),
+        }
+    }
+
+    fn accepts(&self, response: &Response) -> bool {
+        let code_matches = |ranges: &[CodeRange]| ranges.iter().any(|r| r.contains(response.status));
+
+        if !self.match_code.is_empty() && !code_matches(&self.match_code) {
+            return false;
+        }
+        if !self.match_size.is_empty() && !self.match_size.contains(&response.size) {
+            return false;
+        }
+        if !self.match_words.is_empty() && !self.match_words.contains(&response.words) {
+            return false;
+        }
+        if let Some(regex) = &self.match_regex {
+            if !regex.is_match(&response.body) {
+                return false;
+            }
+        }
+
+        let filtered = code_matches(&self.filter_code)
+            || self.filter_size.contains(&response.size)
+            || self.filter_words.contains(&response.words)
+            || self.filter_regex.as_ref().is_some_and(|r| r.is_match(&response.body));
+
+        !filtered
+    }
+}
+
+#[derive(Debug)]
+struct Response {
+    status: u16,
+    size: usize,
+    words: usize,
+    lines: usize,
+    hash: String,
+    body: String,
+    raw: Vec<u8>,
+    final_url: String,
+    location: Option<String>,
+    http_version: String,
+    headers: Vec<(String, String)>,
+    ttfb: Duration,
+    elapsed: Duration,
+    hops: Vec<Hop>,
+}
+
+/// Status line and metadata of the final response, gathered before its body is read.
+#[derive(Debug)]
+struct ResponseHead {
+    status: u16,
+    final_url: String,
+    location: Option<String>,
+    http_version: String,
+    headers: Vec<(String, String)>,
+    ttfb: Duration,
+}
+
+impl Response {
+    fn new(head: ResponseHead, raw: Vec<u8>, elapsed: Duration, hops: Vec<Hop>) -> Self {
+        let body = String::from_utf8_lossy(&raw).into_owned();
+        Response {
+            status: head.status,
+            size: raw.len(),
+            words: body.split_whitespace().count(),
+            lines: body.lines().count(),
+            hash: content_hash(&raw),
+            body,
+            raw,
+            final_url: head.final_url,
+            location: head.location,
+            http_version: head.http_version,
+            headers: head.headers,
+            ttfb: head.ttfb,
+            elapsed,
+            hops,
+        }
+    }
+}
+
+/// One request/response exchange of a redirect chain, recorded when tracing.
+#[derive(Debug)]
+struct Hop {
+    method: Method,
+    url: String,
+    sent_body: bool,
+    status: u16,
+    location: Option<String>,
+    server: Option<String>,
+    size: usize,
+    http_version: String,
+    headers: Vec<(String, String)>,
+    timing: Timing,
+}
+
+/// Connection phases are individual durations; TTFB and total are measured from the start.
+#[derive(Debug)]
+struct Timing {
+    dns: Duration,
+    connect: Duration,
+    tls: Option<Duration>,
+    ttfb: Duration,
+    total: Duration,
+}
+
+#[derive(Debug)]
+struct Job {
+    url: String,
+    dir: String,
+    depth: usize,
+}
+
+#[derive(Debug)]
+struct Probe {
+    job: Job,
+    started: DateTime<Utc>,
+    result: Result<Response, String>,
+}
+
+/// Number of random paths requested per directory to learn what "not found" looks like.
+const BASELINE_PROBES: usize = 3;
+/// Minimum word-set similarity for a response to count as the not-found page.
+const SIMILARITY_THRESHOLD: f64 = 0.9;
+
+/// What a nonexistent resource looks like on a given server directory.
+///
+/// The probe's URL is kept alongside the raw body so both sides of a comparison can have the
+/// reflected parts of *both* requests replaced, which keeps template text that happens to equal
+/// the requested word from breaking the match.
+#[derive(Debug)]
+struct Fingerprint {
+    status: u16,
+    url: String,
+    body: String,
+}
+
+impl Fingerprint {
+    fn from_response(url: &str, response: &Response) -> Self {
+        Fingerprint {
+            status: response.status,
+            url: url.to_string(),
+            body: response.body.clone(),
+        }
+    }
+
+    fn matches(&self, url: &str, response: &Response) -> bool {
+        if self.status != response.status {
+            return false;
+        }
+
+        let urls = [self.url.as_str(), url];
+        let expected = normalize_body(&self.body, &urls);
+        let body = normalize_body(&response.body, &urls);
+        content_hash(expected.as_bytes()) == content_hash(body.as_bytes())
+            || similarity(&tokenize(&expected), &tokenize(&body)) >= SIMILARITY_THRESHOLD
+    }
+}
+
+/// Replaces the parts of each request a not-found page may echo back: the exact URL, its path,
+/// and the last path segment where it stands alone between non-alphanumeric characters.
+fn normalize_body(body: &str, urls: &[&str]) -> String {
+    let mut body = body.to_string();
+    for url in urls {
+        body = body.replace(url, "{url}");
+        let Ok(parsed) = Url::parse(url) else { continue };
+        let path = &url[parsed[..url::Position::BeforePath].len()..];
+        if path.len() > 1 {
+            body = body.replace(path, "{path}");
+        }
+        if let Some(word) = parsed.path().trim_end_matches('/').rsplit('/').next().filter(|w| !w.is_empty()) {
+            body = replace_standalone(&body, word, "{word}");
+        }
+    }
+    body
+}
+
+/// Replaces `word` only where it isn't part of a longer alphanumeric run.
+fn replace_standalone(text: &str, word: &str, replacement: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find(word) {
+        let before = rest[..index].chars().next_back().or_else(|| result.chars().next_back());
+        let after = rest[index + word.len()..].chars().next();
+        result.push_str(&rest[..index]);
+        if before.is_some_and(char::is_alphanumeric) || after.is_some_and(char::is_alphanumeric) {
+            result.push_str(word);
+        } else {
+            result.push_str(replacement);
+        }
+        rest = &rest[index + word.len()..];
+    }
+    result.push_str(rest);
+    result
+}
+
+fn tokenize(body: &str) -> HashSet<String> {
+    body.split_whitespace().map(str::to_string).collect()
+}
+
+/// Jaccard similarity of two word sets.
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let shared = a.intersection(b).count();
+    shared as f64 / (a.len() + b.len() - shared) as f64
+}
+
+fn content_hash(data: &[u8]) -> String {
+    hex::encode(&Sha256::digest(data)[..8])
+}
+
+fn random_word() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(16)
+        .map(|c| char::from(c).to_ascii_lowercase())
+        .collect()
+}
+
+/// Requests a few random paths under `dir` and fingerprints the responses.
+async fn fingerprint_dir(
+    template: &Arc<RequestTemplate>,
+    dir: &str,
+    extensions: &[String],
+    mut exporter: Option<&mut Exporter>,
+) -> Vec<Fingerprint> {
+    let mut urls: Vec<String> = (0..BASELINE_PROBES)
+        .map(|_| format!("{}{}", dir, random_word()))
+        .collect();
+    for ext in extensions {
+        urls.push(format!("{}{}.{}", dir, random_word(), ext.trim_start_matches('.')));
+    }
+
+    let mut probes = JoinSet::new();
+    for url in urls {
+        let job = Job { url, dir: dir.to_string(), depth: 0 };
+        probes.spawn(check_url(Arc::clone(template), job));
+    }
+
+    let mut fingerprints: Vec<Fingerprint> = Vec::new();
+    while let Some(Ok(probe)) = probes.join_next().await {
+        if let Some(exporter) = exporter.as_deref_mut() {
+            exporter.record(&probe, template);
+        }
+        let Ok(response) = &probe.result else {
+            continue;
+        };
+        // Real 404s need no special handling
+        if response.status == 404 {
+            continue;
+        }
+        if fingerprints.iter().any(|f| f.matches(&probe.job.url, response)) {
+            continue;
+        }
+
+        eprintln!(
+            "Soft-404 baseline for {}: {} [Size: {}, Words: {}]",
+            dir, response.status, response.size, response.words
+        );
+        fingerprints.push(Fingerprint::from_response(&probe.job.url, response));
+    }
+
+    fingerprints
+}
+
+fn parse_interval(s: &str) -> Result<Duration, String> {
+    let (number, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => s.split_at(i),
+        None => (s, "s"),
+    };
+    let value: u64 = number.parse().map_err(|_| format!("invalid interval '{}'", s))?;
+    let seconds = match unit {
+        "s" => value,
+        "m" => value * 60,
+        "h" => value * 3600,
+        _ => return Err(format!("invalid interval unit in '{}', expected s, m or h", s)),
+    };
+
+    if seconds == 0 {
+        return Err("interval must be greater than zero".to_string());
+    }
+    Ok(Duration::from_secs(seconds))
+}
+
+fn fail(message: String) -> ! {
+    eprintln!("Error: {}", message);
+    std::process::exit(1);
+}
+
+fn parse_headers(raw: &[String], cookies: &[String]) -> Result<HeaderMap, String> {
+    let mut headers = HeaderMap::new();
+
+    for header in raw {
+        let (name, value) = header
+            .split_once(':')
+            .ok_or_else(|| format!("invalid header '{}', expected 'Name: value'", header))?;
+        let name = HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|e| format!("invalid header name in '{}': {}", header, e))?;
+        let value = HeaderValue::from_str(value.trim())
+            .map_err(|e| format!("invalid header value in '{}': {}", header, e))?;
+        headers.append(name, value);
+    }
+
+    if !cookies.is_empty() {
+        let value = HeaderValue::from_str(&cookies.join("; "))
+            .map_err(|e| format!("invalid cookie: {}", e))?;
+        headers.insert(COOKIE, value);
+    }
+
+    Ok(headers)
+}
+
+fn read_body(args: &RequestArgs) -> io::Result<Option<Vec<u8>>> {
+    match (&args.data, &args.data_file) {
+        (Some(data), _) => Ok(Some(data.clone().into_bytes())),
+        (None, Some(path)) if path == "-" => {
+            let mut buffer = Vec::new();
+            io::stdin().read_to_end(&mut buffer)?;
+            Ok(Some(buffer))
+        }
+        (None, Some(path)) => fs::read(path).map(Some),
+        (None, None) => Ok(None),
+    }
+}
+
+fn redirect_policy(mode: RedirectMode) -> Policy {
+    match mode {
+        RedirectMode::Follow => Policy::limited(10),
+        RedirectMode::Off => Policy::none(),
+        RedirectMode::SameHost => Policy::custom(|attempt| {
+            if attempt.previous().len() >= 10 {
+                attempt.error("too many redirects")
+            } else if attempt.url().host_str() == attempt.previous()[0].host_str() {
+                attempt.follow()
+            } else {
+                attempt.stop()
+            }
+        }),
+    }
+}
+
+fn build_template(
+    args: &RequestArgs,
+    client_args: &ClientArgs,
+    redirects: RedirectMode,
+    trace: bool,
+    throttle: Throttle,
+) -> Result<RequestTemplate, String> {
+    let method = Method::from_bytes(args.method.to_uppercase().as_bytes())
+        .map_err(|_| format!("invalid HTTP method '{}'", args.method))?;
+
+    let headers = parse_headers(&args.headers, &args.cookies)?;
+
+    // One client for every request so connections are pooled and reused
+    let client = client_args
+        .builder()
+        .default_headers(headers.clone())
+        .redirect(redirect_policy(redirects))
+        .build()
+        .map_err(|e| format!("failed to build HTTP client: {}", e))?;
+
+    let body = read_body(args).map_err(|e| format!("failed to read request body: {}", e))?;
+
+    let auth = match (&args.user, &args.bearer) {
+        (Some(credentials), _) => match credentials.split_once(':') {
+            Some((user, pass)) => Some(Auth::Basic(user.to_string(), Some(pass.to_string()))),
+            None => Some(Auth::Basic(credentials.clone(), None)),
+        },
+        (None, Some(token)) => Some(Auth::Bearer(token.clone())),
+        (None, None) => None,
+    };
+
+    Ok(RequestTemplate {
+        client,
+        method,
+        body,
+        auth,
+        headers,
+        user_agent: client_args.user_agent.clone(),
+        redirects,
+        timeout: Duration::from_secs(client_args.timeout),
+        trace,
+        throttle,
+    })
+}
+
+/// Reads the wordlist, skipping blank lines and `#` comments.
+fn read_words(path: &str) -> io::Result<Vec<String>> {
+    let reader = BufReader::new(File::open(path)?);
+    Ok(reader
+        .lines()
+        .map_while(Result::ok)
+        .map(|line| line.trim().trim_start_matches('/').to_string())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect())
+}
+
+/// Expands each word into itself plus one candidate per extension.
+fn expand_words(words: &[String], extensions: &[String]) -> Vec<String> {
+    let mut expanded = Vec::with_capacity(words.len() * (extensions.len() + 1));
+
+    for word in words {
+        expanded.push(word.clone());
+        if word.ends_with('/') {
+            continue;
+        }
+        for ext in extensions {
+            expanded.push(format!("{}.{}", word, ext.trim_start_matches('.')));
+        }
+    }
+
+    expanded
+}
+
+/// Returns the directory URL to recurse into when a response reveals one: the slash form
+/// answered with a 2xx, 401 or 403, or a redirect from the bare name to the slash form.
+fn discovered_directory(url: &str, response: &Response) -> Option<String> {
+    let exists = matches!(response.status, 200..=299 | 401 | 403);
+    if url.ends_with('/') {
+        return exists.then(|| url.to_string());
+    }
+
+    let with_slash = format!("{}/", url);
+    let redirected = (300..400).contains(&response.status) && response.location.as_deref() == Some(with_slash.as_str());
+    let followed = exists && response.final_url == with_slash;
+    (redirected || followed).then_some(with_slash)
+}
+
+fn location_of(url: &Url, headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(LOCATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| url.join(v).ok())
+        .map(|u| u.to_string())
+}
+
+fn format_ms(duration: Duration) -> String {
+    format!("{:.2}ms", duration.as_secs_f64() * 1000.0)
+}
+
+async fn send_over<S>(stream: S, request: hyper::Request<hyper::Body>) -> Result<hyper::Response<hyper::Body>, String>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (mut sender, connection) = hyper::client::conn::handshake(stream)
+        .await
+        .map_err(|e| e.to_string())?;
+    tokio::spawn(async move {
+        let _ = connection.await;
+    });
+    sender.send_request(request).await.map_err(|e| e.to_string())
+}
+
+/// Performs a single request on a fresh connection, timing each phase along the way.
+async fn timed_exchange(
+    template: &RequestTemplate,
+    method: &Method,
+    url: &Url,
+    with_body: bool,
+    with_auth: bool,
+) -> Result<(Hop, Vec<u8>), RequestError> {
+    let host = url.host_str().ok_or("URL has no host")?;
+    let port = url.port_or_known_default().ok_or("URL has no port")?;
+
+    let mut request = hyper::Request::builder()
+        .method(method.as_str())
+        .uri(&url[url::Position::BeforePath..])
+        .header(CONNECTION, "close");
+    for (name, value) in template.request_headers(url, with_auth) {
+        request = request.header(name, value);
+    }
+    let body = match &template.body {
+        Some(body) if with_body => hyper::Body::from(body.clone()),
+        _ => hyper::Body::empty(),
+    };
+    let request = request.body(body).map_err(|e| e.to_string())?;
+
+    let start = Instant::now();
+    let addr = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| RequestError::retryable(format!("DNS lookup failed: {}", e)))?
+        .next()
+        .ok_or_else(|| format!("no addresses found for {}", host))?;
+    let dns = start.elapsed();
+
+    let stream = TcpStream::connect(addr)
+        .await
+        .map_err(|e| RequestError::retryable(format!("connect failed: {}", e)))?;
+    let connect = start.elapsed() - dns;
+
+    let (response, tls) = if url.scheme() == "https" {
+        let connector = native_tls::TlsConnector::new().map_err(|e| e.to_string())?;
+        let tls_start = Instant::now();
+        let stream = TlsConnector::from(connector)
+            .connect(host, stream)
+            .await
+            .map_err(|e| format!("TLS handshake failed: {}", e))?;
+        let tls = tls_start.elapsed();
+        (send_over(stream, request).await?, Some(tls))
+    } else {
+        (send_over(stream, request).await?, None)
+    };
+    let ttfb = start.elapsed();
+
+    let status = response.status().as_u16();
+    let location = location_of(url, response.headers());
+    let server = response
+        .headers()
+        .get(SERVER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let http_version = format!("{:?}", response.version());
+    let headers = header_pairs(response.headers());
+    let bytes = hyper::body::to_bytes(response.into_body())
+        .await
+        .map_err(|e| e.to_string())?;
+    let total = start.elapsed();
+
+    let hop = Hop {
+        method: method.clone(),
+        url: url.to_string(),
+        sent_body: with_body && template.body.is_some(),
+        status,
+        location,
+        server,
+        size: bytes.len(),
+        http_version,
+        headers,
+        timing: Timing { dns, connect, tls, ttfb, total },
+    };
+    Ok((hop, bytes.to_vec()))
+}
+
+/// Follows redirects by hand so that every hop can be timed and reported.
+async fn traced_request(template: &RequestTemplate, url: &str) -> Result<Response, RequestError> {
+    let start = Instant::now();
+    let origin = Url::parse(url).map_err(|e| e.to_string())?;
+    let mut current = origin.clone();
+    let mut method = template.method.clone();
+    let mut with_body = true;
+    let mut hops = Vec::new();
+
+    loop {
+        let with_auth = current.host_str() == origin.host_str();
+        let (hop, bytes) = timed_exchange(template, &method, &current, with_body, with_auth).await?;
+
+        let status = hop.status;
+        let location = hop.location.clone();
+        let next = location
+            .as_deref()
+            .filter(|_| (300..400).contains(&status))
+            .and_then(|l| Url::parse(l).ok())
+            .filter(|next| template.redirects.allows(&origin, next));
+        hops.push(hop);
+
+        let Some(next) = next else {
+            let elapsed = start.elapsed();
+            let last = &hops[hops.len() - 1];
+            let head = ResponseHead {
+                status,
+                final_url: current.to_string(),
+                location,
+                http_version: last.http_version.clone(),
+                headers: last.headers.clone(),
+                ttfb: elapsed.saturating_sub(last.timing.total - last.timing.ttfb),
+            };
+            return Ok(Response::new(head, bytes, elapsed, hops));
+        };
+        if hops.len() > MAX_REDIRECTS {
+            return Err("too many redirects".into());
+        }
+
+        // Same rewriting browsers apply: 303 always, and 301/302 for POST, become a bodiless GET
+        if status == 303 || (matches!(status, 301 | 302) && method == Method::POST) {
+            method = Method::GET;
+            with_body = false;
+        }
+        current = next;
+    }
+}
+
+fn header_pairs(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| (name.to_string(), String::from_utf8_lossy(value.as_bytes()).into_owned()))
+        .collect()
+}
+
+async fn pooled_request(template: &RequestTemplate, url: &str) -> Result<Response, RequestError> {
+    let start = Instant::now();
+    let response = template.build(url).send().await?;
+    let head = ResponseHead {
+        status: response.status().as_u16(),
+        final_url: response.url().to_string(),
+        location: location_of(response.url(), response.headers()),
+        http_version: format!("{:?}", response.version()),
+        headers: header_pairs(response.headers()),
+        ttfb: start.elapsed(),
+    };
+    let bytes = response.bytes().await.map_err(|e| e.to_string())?;
+
+    Ok(Response::new(head, bytes.to_vec(), start.elapsed(), Vec::new()))
+}
+
+async fn send_once(template: &RequestTemplate, url: &str) -> Result<Response, RequestError> {
+    if template.trace {
+        match tokio::time::timeout(template.timeout, traced_request(template, url)).await {
+            Ok(result) => result,
+            Err(_) => Err("request timed out".into()),
+        }
+    } else {
+        pooled_request(template, url).await
+    }
+}
+
+fn response_attempt(result: &Result<Response, RequestError>) -> Attempt {
+    match result {
+        Ok(response) => status_attempt(response.status, header_value(&response.headers, RETRY_AFTER.as_str())),
+        Err(e) if e.retryable => Attempt::Retry(None),
+        Err(_) => Attempt::Done,
+    }
+}
+
+async fn check_url(template: Arc<RequestTemplate>, job: Job) -> Probe {
+    let started = Utc::now();
+    let result = template
+        .throttle
+        .run(&job.url, || send_once(&template, &job.url), response_attempt)
+        .await
+        .map_err(|e| e.message);
+
+    Probe { job, started, result }
+}
+
+fn print_hops(hops: &[Hop]) {
+    for hop in hops {
+        let mut line = format!("    {} {}", hop.status, hop.url);
+        if let Some(location) = &hop.location {
+            line.push_str(&format!(" -> {}", location));
+        }
+        println!("{}", line);
+
+        let timing = &hop.timing;
+        println!(
+            "        dns {} | connect {} | tls {} | ttfb {} | total {} | {} bytes | server: {}",
+            format_ms(timing.dns),
+            format_ms(timing.connect),
+            timing.tls.map(format_ms).unwrap_or_else(|| "-".to_string()),
+            format_ms(timing.ttfb),
+            format_ms(timing.total),
+            hop.size,
+            hop.server.as_deref().unwrap_or("-")
+        );
+    }
+}
+
+fn report(probe: &Probe, filters: &Filters, baseline: &[Fingerprint], mode: Soft404Mode, verbose: bool) -> bool {
+    match &probe.result {
+        Ok(response) => {
+            if !filters.accepts(response) {
+                return false;
+            }
+
+            let soft_404 = baseline.iter().any(|f| f.matches(&probe.job.url, response));
+            if soft_404 && mode == Soft404Mode::Hide {
+                return false;
+            }
+
+            let mut line = format!(
+                "{} - {} [Size: {}, Words: {}, Lines: {}, Hash: {}]",
+                probe.job.url, response.status, response.size, response.words, response.lines, response.hash
+            );
+            if let Some(location) = &response.location {
+                line.push_str(&format!(" -> {}", location));
+            }
+            if soft_404 {
+                line.push_str(" [soft-404]");
+            }
+            println!("{}", line);
+            if verbose {
+                print_hops(&response.hops);
+            }
+            !soft_404
+        }
+        Err(e) => {
+            println!("{} - Error: {}", probe.job.url, e);
+            false
+        }
+    }
+}
+
+/// A health-check suite; check URLs may be relative to `base_url`.
+#[derive(Debug, Deserialize)]
+struct Suite {
+    base_url: Option<String>,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    checks: Vec<CheckSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckSpec {
+    name: Option<String>,
+    url: String,
+    #[serde(default = "default_method")]
+    method: String,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    body: Option<String>,
+    /// Accepted status codes; any 2xx when empty
+    #[serde(default)]
+    status: Vec<u16>,
+    body_regex: Option<String>,
+    /// JSON pointer -> expected value
+    #[serde(default)]
+    json: BTreeMap<String, Value>,
+    max_latency_ms: Option<u64>,
+    /// Set to false to assert on the redirect response itself (e.g. `status = [301]`)
+    #[serde(default = "default_follow_redirects")]
+    follow_redirects: bool,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+fn default_follow_redirects() -> bool {
+    true
+}
+
+impl CheckSpec {
+    fn display_name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| format!("{} {}", self.method, self.url))
+    }
+}
+
+#[derive(Debug)]
+struct CheckResult {
+    name: String,
+    latency: Duration,
+    failures: Vec<String>,
+    error: Option<String>,
+}
+
+impl CheckResult {
+    fn passed(&self) -> bool {
+        self.error.is_none() && self.failures.is_empty()
+    }
+}
+
+fn load_suite(path: &str) -> Result<Suite, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("failed to read '{}': {}", path, e))?;
+    parse_suite(path, &content)
+}
+
+/// Parses a suite as JSON when `path` ends in `.json`, TOML otherwise.
+fn parse_suite(path: &str, content: &str) -> Result<Suite, String> {
+    let is_json = Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    if is_json {
+        serde_json::from_str(content).map_err(|e| format!("invalid suite '{}': {}", path, e))
+    } else {
+        toml::from_str(content).map_err(|e| format!("invalid suite '{}': {}", path, e))
+    }
+}
+
+async fn run_check(
+    client: Client,
+    throttle: Arc<Throttle>,
+    suite_headers: Arc<BTreeMap<String, String>>,
+    base: Option<Url>,
+    spec: CheckSpec,
+) -> CheckResult {
+    let name = spec.display_name();
+    let mut result = CheckResult {
+        name,
+        latency: Duration::ZERO,
+        failures: Vec::new(),
+        error: None,
+    };
+
+    let url = match &base {
+        Some(base) => base.join(&spec.url),
+        None => Url::parse(&spec.url),
+    };
+    let url = match url {
+        Ok(url) => url,
+        Err(e) => {
+            result.error = Some(format!("invalid URL '{}': {}", spec.url, e));
+            return result;
+        }
+    };
+    let method = match Method::from_bytes(spec.method.to_uppercase().as_bytes()) {
+        Ok(method) => method,
+        Err(_) => {
+            result.error = Some(format!("invalid HTTP method '{}'", spec.method));
+            return result;
+        }
+    };
+    let body_regex = match spec.body_regex.as_deref().map(Regex::new).transpose() {
+        Ok(regex) => regex,
+        Err(e) => {
+            result.error = Some(format!("invalid body_regex: {}", e));
+            return result;
+        }
+    };
+
+    let url_string = url.to_string();
+    let mut request = client.request(method, url);
+    for (name, value) in suite_headers.iter().chain(spec.headers.iter()) {
+        request = request.header(name, value);
+    }
+    if let Some(body) = &spec.body {
+        request = request.body(body.clone());
+    }
+
+    // Restarted on every attempt, so back-off and rate-limit waits don't count as latency.
+    let attempt_start = Mutex::new(Instant::now());
+    let send = || {
+        *attempt_start.lock().unwrap() = Instant::now();
+        async {
+            match request.try_clone() {
+                Some(request) => request.send().await,
+                None => unreachable!("suite request bodies are always buffered"),
+            }
+        }
+    };
+    let response = match throttle.run(&url_string, send, reqwest_attempt).await {
+        Ok(response) => response,
+        Err(e) => {
+            result.error = Some(e.to_string());
+            return result;
+        }
+    };
+    let status = response.status().as_u16();
+    let body = match response.text().await {
+        Ok(body) => body,
+        Err(e) => {
+            result.error = Some(e.to_string());
+            return result;
+        }
+    };
+    result.latency = attempt_start.lock().unwrap().elapsed();
+
+    result.failures = evaluate_check(&spec, body_regex.as_ref(), status, &body, result.latency);
+    result
+}
+
+/// Checks a response against the spec's assertions, returning one message per failed assertion.
+fn evaluate_check(spec: &CheckSpec, body_regex: Option<&Regex>, status: u16, body: &str, latency: Duration) -> Vec<String> {
+    let mut failures = Vec::new();
+    let status_ok = if spec.status.is_empty() {
+        (200..300).contains(&status)
+    } else {
+        spec.status.contains(&status)
+    };
+    if !status_ok {
+        failures.push(format!("unexpected status {}", status));
+    }
+
+    if let Some(max) = spec.max_latency_ms {
+        if latency > Duration::from_millis(max) {
+            failures.push(format!("latency {} exceeds {}ms", format_ms(latency), max));
+        }
+    }
+
+    if let Some(regex) = body_regex {
+        if !regex.is_match(body) {
+            failures.push(format!("body does not match /{}/", regex));
+        }
+    }
+
+    if !spec.json.is_empty() {
+        match serde_json::from_str::<Value>(body) {
+            Ok(document) => {
+                for (pointer, expected) in &spec.json {
+                    match document.pointer(pointer) {
+                        Some(actual) if actual == expected => {}
+                        Some(actual) => failures.push(format!("{} is {}, expected {}", pointer, actual, expected)),
+                        None => failures.push(format!("{} is missing", pointer)),
+                    }
+                }
+            }
+            Err(e) => failures.push(format!("body is not valid JSON: {}", e)),
+        }
+    }
+
+    failures
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn junit_report(suite_name: &str, results: &[CheckResult], elapsed: Duration) -> String {
+    let failures = results.iter().filter(|r| r.error.is_none() && !r.failures.is_empty()).count();
+    let errors = results.iter().filter(|r| r.error.is_some()).count();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">\n",
+        xml_escape(suite_name),
+        results.len(),
+        failures,
+        errors,
+        elapsed.as_secs_f64()
+    ));
+
+    for result in results {
+        xml.push_str(&format!(
+            "  <testcase name=\"{}\" classname=\"http-status\" time=\"{:.3}\"",
+            xml_escape(&result.name),
+            result.latency.as_secs_f64()
+        ));
+        if result.passed() {
+            xml.push_str("/>\n");
+            continue;
+        }
+
+        xml.push_str(">\n");
+        if let Some(error) = &result.error {
+            xml.push_str(&format!("    <error message=\"{}\"/>\n", xml_escape(error)));
+        } else {
+            let details = result.failures.join("\n");
+            xml.push_str(&format!(
+                "    <failure message=\"{}\">{}</failure>\n",
+                xml_escape(&result.failures[0]),
+                xml_escape(&details)
+            ));
+        }
+        xml.push_str("  </testcase>\n");
+    }
+
+    xml.push_str("</testsuite>\n");
+    xml
+}
+
+/// Runs every check of the suite and returns whether all of them passed.
+async fn run_suite(args: &CheckArgs) -> bool {
+    let suite = load_suite(&args.suite).unwrap_or_else(|e| fail(e));
+    let base = suite
+        .base_url
+        .as_deref()
+        .map(|base| Url::parse(base).unwrap_or_else(|e| fail(format!("invalid base_url '{}': {}", base, e))));
+
+    let client = args.client.build_client();
+    let no_redirect_client = args
+        .client
+        .builder()
+        .redirect(Policy::none())
+        .build()
+        .unwrap_or_else(|e| fail(format!("failed to build HTTP client: {}", e)));
+    let throttle = Arc::new(Throttle::new(&args.retry));
+    let suite_headers = Arc::new(suite.headers);
+
+    let start = Instant::now();
+    let mut results: Vec<Option<CheckResult>> = (0..suite.checks.len()).map(|_| None).collect();
+    let mut tasks = JoinSet::new();
+
+    for (index, spec) in suite.checks.into_iter().enumerate() {
+        while tasks.len() >= args.client.concurrency {
+            if let Some(Ok((index, result))) = tasks.join_next().await {
+                results[index] = Some(result);
+            }
+        }
+
+        let client = if spec.follow_redirects { &client } else { &no_redirect_client };
+        let check = run_check(client.clone(), Arc::clone(&throttle), Arc::clone(&suite_headers), base.clone(), spec);
+        tasks.spawn(async move { (index, check.await) });
+    }
+
+    while let Some(joined) = tasks.join_next().await {
+        if let Ok((index, result)) = joined {
+            results[index] = Some(result);
+        }
+    }
+    let elapsed = start.elapsed();
+    let results: Vec<CheckResult> = results.into_iter().flatten().collect();
+
+    for result in &results {
+        let latency = format!("({})", format_ms(result.latency)).bright_black();
+        if result.passed() {
+            println!("{} {} {}", "✓".green(), result.name, latency);
+            continue;
+        }
+
+        println!("{} {} {}", "✗".red(), result.name, latency);
+        if let Some(error) = &result.error {
+            println!("    {}", error.red());
+        }
+        for failure in &result.failures {
+            println!("    {}", failure.red());
+        }
+    }
+
+    let passed = results.iter().filter(|r| r.passed()).count();
+    let failed = results.len() - passed;
+    println!();
+    println!(
+        "{} passed, {} failed in {}",
+        passed.to_string().green(),
+        failed.to_string().red(),
+        format_ms(elapsed)
+    );
+
+    if let Some(path) = &args.junit {
+        if let Err(e) = fs::write(path, junit_report(&args.suite, &results, elapsed)) {
+            eprintln!("Error: failed to write JUnit report '{}': {}", path, e);
+            return false;
+        }
+    }
+
+    failed == 0
+}
+
+/// Request template for the URL-list modes (scan and watch), built from the top-level flags.
+fn url_list_template(args: &Args, trace: bool) -> Arc<RequestTemplate> {
+    Arc::new(
+        build_template(
+            &args.request,
+            &args.client,
+            args.redirects,
+            trace,
+            Throttle::new(&args.retry),
+        )
+        .unwrap_or_else(|e| fail(e)),
+    )
+}
+
+async fn run_scan(args: &Args, base_url: &str, paths_file: &str) {
+    // Exports need every redirect hop, so they trace requests just like verbose mode
+    let template = url_list_template(args, args.verbose || args.output.is_some());
+    let filters = Filters::from_args(args);
+
+    let words = read_words(paths_file).unwrap_or_else(|e| fail(format!("failed to open '{}': {}", paths_file, e)));
+    let candidates = expand_words(&words, &args.extensions);
+    let mut exporter = args
+        .output
+        .as_deref()
+        .map(|path| Exporter::new(path, args.format, args.include_bodies));
+
+    let base_url = format!("{}/", base_url.trim_end_matches('/'));
+    let mut baselines: HashMap<String, Vec<Fingerprint>> = HashMap::new();
+    if args.soft_404 != Soft404Mode::Off {
+        let baseline = fingerprint_dir(&template, &base_url, &args.extensions, exporter.as_mut()).await;
+        baselines.insert(base_url.clone(), baseline);
+    }
+
+    let mut pending: VecDeque<Job> = candidates
+        .iter()
+        .map(|candidate| Job {
+            url: format!("{}{}", base_url, candidate),
+            dir: base_url.clone(),
+            depth: 0,
+        })
+        .collect();
+    let mut scanned_dirs = HashSet::from([base_url]);
+
+    // Create concurrent tasks with limit
+    let mut tasks = JoinSet::new();
+
+    while !pending.is_empty() || !tasks.is_empty() {
+        // Keep the pool full
+        while tasks.len() < args.client.concurrency {
+            match pending.pop_front() {
+                Some(job) => {
+                    tasks.spawn(check_url(Arc::clone(&template), job));
+                }
+                None => break,
+            }
+        }
+
+        let Some(Ok(probe)) = tasks.join_next().await else {
+            continue;
+        };
+
+        if let Some(exporter) = exporter.as_mut() {
+            exporter.record(&probe, &template);
+        }
+
+        let baseline = baselines.get(&probe.job.dir).map(Vec::as_slice).unwrap_or_default();
+        if !report(&probe, &filters, baseline, args.soft_404, args.verbose) || probe.job.depth >= args.recursion_depth {
+            continue;
+        }
+
+        let Some(dir) = probe.result.as_ref().ok().and_then(|r| discovered_directory(&probe.job.url, r)) else {
+            continue;
+        };
+        if !scanned_dirs.insert(dir.clone()) {
+            continue;
+        }
+
+        if args.soft_404 != Soft404Mode::Off {
+            let baseline = fingerprint_dir(&template, &dir, &args.extensions, exporter.as_mut()).await;
+            baselines.insert(dir.clone(), baseline);
+        }
+        for candidate in &candidates {
+            pending.push_back(Job {
+                url: format!("{}{}", dir, candidate),
+                dir: dir.clone(),
+                depth: probe.job.depth + 1,
+            });
+        }
+    }
+
+    if let (Some(exporter), Some(path)) = (exporter, &args.output) {
+        if let Err(e) = exporter.write(path) {
+            fail(format!("failed to write '{}': {}", path, e));
+        }
+    }
+}
+
+/// Allow/Disallow rules from robots.txt that apply to this crawler.
+#[derive(Debug, Default)]
+struct Robots {
+    rules: Vec<(bool, String, Regex)>,
+}
+
+impl Robots {
+    /// Uses the groups naming our product token (compared case-insensitively, per RFC 9309)
+    /// if any, otherwise the `*` group.
+    fn parse(text: &str, user_agent: &str) -> Self {
+        let token = product_token(user_agent);
+        let mut specific = Vec::new();
+        let mut wildcard = Vec::new();
+        let mut agents: Vec<String> = Vec::new();
+        let mut in_rules = false;
+        let mut named = false;
+
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let (key, value) = (key.trim().to_lowercase(), value.trim());
+
+            match key.as_str() {
+                "user-agent" => {
+                    // A user-agent line after rules starts a new group
+                    if in_rules {
+                        agents.clear();
+                        in_rules = false;
+                    }
+                    let agent = product_token(value);
+                    named |= agent == token;
+                    agents.push(agent);
+                }
+                "allow" | "disallow" => {
+                    in_rules = true;
+                    if value.is_empty() {
+                        continue;
+                    }
+                    let rule = (key == "allow", value.to_string());
+                    if agents.contains(&token) {
+                        specific.push(rule.clone());
+                    }
+                    if agents.iter().any(|a| a == "*") {
+                        wildcard.push(rule);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let rules = if named { specific } else { wildcard };
+        Robots {
+            rules: rules
+                .into_iter()
+                .filter_map(|(allow, pattern)| {
+                    let regex = robots_pattern(&pattern)?;
+                    Some((allow, pattern, regex))
+                })
+                .collect(),
+        }
+    }
+
+    /// Longest matching rule wins, with Allow winning ties.
+    fn allows(&self, url: &Url) -> bool {
+        let path = &url[url::Position::BeforePath..];
+        self.rules
+            .iter()
+            .filter(|(_, _, regex)| regex.is_match(path))
+            .max_by_key(|(allow, pattern, _)| (pattern.len(), *allow))
+            .is_none_or(|(allow, _, _)| *allow)
+    }
+}
+
+/// The product token of a User-Agent (`http-status/1.0` -> `http-status`), lowercased.
+fn product_token(user_agent: &str) -> String {
+    user_agent.split(['/', ' ']).next().unwrap_or_default().trim().to_lowercase()
+}
+
+/// Converts a robots.txt path pattern with `*` and `$` into an anchored regex.
+fn robots_pattern(pattern: &str) -> Option<Regex> {
+    let (body, anchored) = match pattern.strip_suffix('$') {
+        Some(body) => (body, "$"),
+        None => (pattern, ""),
+    };
+    let body = body.split('*').map(regex::escape).collect::<Vec<_>>().join(".*");
+    Regex::new(&format!("^{}{}", body, anchored)).ok()
+}
+
+/// Fetches robots.txt through the crawl's throttle, so `--rate` and `--per-host` apply to it too.
+async fn fetch_robots(client: &Client, throttle: &Throttle, start: &Url, user_agent: &str) -> Robots {
+    let Ok(robots_url) = start.join("/robots.txt") else {
+        return Robots::default();
+    };
+
+    let send = || client.get(robots_url.clone()).send();
+    match throttle.run(robots_url.as_str(), send, reqwest_attempt).await {
+        Ok(response) if response.status().is_success() => match response.text().await {
+            Ok(text) => Robots::parse(&text, user_agent),
+            Err(_) => Robots::default(),
+        },
+        _ => Robots::default(),
+    }
+}
+
+/// Collects every link target from the page, resolved against its URL.
+fn extract_links(page: &Url, html: &str) -> Vec<Url> {
+    let document = Html::parse_document(html);
+    let mut links = Vec::new();
+
+    for (selector, attribute) in [("a[href]", "href"), ("img[src]", "src"), ("script[src]", "src"), ("link[href]", "href")] {
+        let Ok(selector) = Selector::parse(selector) else {
+            continue;
+        };
+        for element in document.select(&selector) {
+            let Some(target) = element.value().attr(attribute) else {
+                continue;
+            };
+            if let Ok(mut url) = page.join(target.trim()) {
+                if matches!(url.scheme(), "http" | "https") {
+                    url.set_fragment(None);
+                    links.push(url);
+                }
+            }
+        }
+    }
+
+    links
+}
+
+/// Fetches a link, returning its status and, for crawlable HTML pages, the links it contains.
+async fn crawl_fetch(
+    client: Client,
+    throttle: Arc<Throttle>,
+    url: Url,
+    depth: usize,
+    parse: bool,
+) -> (Url, usize, Result<(u16, Option<Vec<Url>>), String>) {
+    let result = async {
+        let response = throttle
+            .run(url.as_str(), || client.get(url.clone()).send(), reqwest_attempt)
+            .await
+            .map_err(|e| e.to_string())?;
+        let status = response.status().as_u16();
+        let is_html = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/html"));
+
+        if !parse || !is_html || status >= 400 {
+            return Ok((status, None));
+        }
+
+        let final_url = response.url().clone();
+        let body = response.text().await.map_err(|e| e.to_string())?;
+        Ok((status, Some(extract_links(&final_url, &body))))
+    }
+    .await;
+
+    (url, depth, result)
+}
+
+/// Crawls the site and returns whether no broken links were found.
+async fn run_crawl(args: &CrawlArgs) -> bool {
+    let client = args.client.build_client();
+    let throttle = Arc::new(Throttle::new(&args.retry));
+    let mut start = Url::parse(&args.url).unwrap_or_else(|e| fail(format!("invalid URL '{}': {}", args.url, e)));
+    start.set_fragment(None);
+    let origin = start.origin();
+
+    let robots = if args.ignore_robots {
+        Robots::default()
+    } else {
+        fetch_robots(&client, &throttle, &start, &args.client.user_agent).await
+    };
+
+    let mut referrers: HashMap<Url, Vec<Url>> = HashMap::new();
+    let mut seen: HashSet<Url> = HashSet::from([start.clone()]);
+    let mut pending: VecDeque<(Url, usize)> = VecDeque::from([(start, 0)]);
+    let mut results: BTreeMap<Url, Result<u16, String>> = BTreeMap::new();
+    let mut crawled = 0;
+    let mut skipped_by_robots = 0;
+    let mut tasks = JoinSet::new();
+
+    while !pending.is_empty() || !tasks.is_empty() {
+        while tasks.len() < args.client.concurrency {
+            let Some((url, depth)) = pending.pop_front() else {
+                break;
+            };
+            let internal = url.origin() == origin;
+            let parse = internal && depth < args.max_depth;
+            tasks.spawn(crawl_fetch(client.clone(), Arc::clone(&throttle), url, depth, parse));
+        }
+
+        let Some(Ok((url, depth, result))) = tasks.join_next().await else {
+            continue;
+        };
+
+        let links = match result {
+            Ok((status, links)) => {
+                results.insert(url.clone(), Ok(status));
+                match links {
+                    // Pages beyond the limit are still checked, just not followed
+                    Some(links) if crawled < args.max_pages => {
+                        crawled += 1;
+                        links
+                    }
+                    _ => continue,
+                }
+            }
+            Err(e) => {
+                results.insert(url.clone(), Err(e));
+                continue;
+            }
+        };
+
+        for link in links {
+            referrers.entry(link.clone()).or_default().push(url.clone());
+            if !seen.insert(link.clone()) {
+                continue;
+            }
+
+            let internal = link.origin() == origin;
+            if !internal && !args.external {
+                continue;
+            }
+            if internal && !robots.allows(&link) {
+                skipped_by_robots += 1;
+                continue;
+            }
+            pending.push_back((link, depth + 1));
+        }
+    }
+
+    let mut broken = 0;
+    for (url, result) in &results {
+        let problem = match result {
+            Ok(status) if *status < 400 => continue,
+            Ok(status) => status.to_string(),
+            Err(e) => format!("Error: {}", e),
+        };
+
+        broken += 1;
+        println!("{} {} - {}", "✗".red(), url, problem.red());
+        let mut sources = referrers.get(url).cloned().unwrap_or_default();
+        sources.sort();
+        sources.dedup();
+        for source in sources {
+            println!("    referenced by {}", source);
+        }
+    }
+
+    println!();
+    println!(
+        "Crawled {} pages, checked {} links, skipped {} disallowed by robots.txt",
+        crawled,
+        results.len(),
+        skipped_by_robots
+    );
+    if broken == 0 {
+        println!("{} No broken links", "✓".green());
+    } else {
+        println!("{} {} broken links", "✗".red(), broken);
+    }
+
+    broken == 0
+}
+
+fn duration_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+fn har_headers(headers: &[(String, String)]) -> Value {
+    headers
+        .iter()
+        .map(|(name, value)| json!({ "name": name, "value": value }))
+        .collect()
+}
+
+fn header_value<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+/// HAR timings; only traced requests know the connection phases, the rest are -1 (not applicable).
+fn har_timings(response: &Response) -> Value {
+    match response.hops.last() {
+        Some(hop) => hop_timings(&hop.timing, response.elapsed.saturating_sub(hop.timing.total)),
+        None => json!({
+            "blocked": -1.0,
+            "dns": -1.0,
+            "connect": -1.0,
+            "ssl": -1.0,
+            "send": 0.0,
+            "wait": duration_ms(response.ttfb),
+            "receive": duration_ms(response.elapsed.saturating_sub(response.ttfb)),
+        }),
+    }
+}
+
+fn hop_timings(timing: &Timing, blocked: Duration) -> Value {
+    let tls = timing.tls.unwrap_or_default();
+    json!({
+        "blocked": duration_ms(blocked),
+        "dns": duration_ms(timing.dns),
+        // HAR counts the TLS handshake as part of connect
+        "connect": duration_ms(timing.connect + tls),
+        "ssl": timing.tls.map_or(-1.0, duration_ms),
+        "send": 0.0,
+        "wait": duration_ms(timing.ttfb.saturating_sub(timing.dns + timing.connect + tls)),
+        "receive": duration_ms(timing.total.saturating_sub(timing.ttfb)),
+    })
+}
+
+fn har_cookie(pair: &str) -> Option<Value> {
+    let (name, value) = pair.trim().split_once('=')?;
+    Some(json!({ "name": name.trim(), "value": value.trim() }))
+}
+
+/// Cookies sent in the `Cookie` request header.
+fn request_cookies(headers: &[(String, String)]) -> Value {
+    headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case(COOKIE.as_str()))
+        .flat_map(|(_, value)| value.split(';'))
+        .filter_map(har_cookie)
+        .collect()
+}
+
+/// Cookies set by `Set-Cookie` response headers; attributes such as `Path` are left out.
+fn response_cookies(headers: &[(String, String)]) -> Value {
+    headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case(SET_COOKIE.as_str()))
+        .filter_map(|(_, value)| value.split(';').next())
+        .filter_map(har_cookie)
+        .collect()
+}
+
+/// Body as text, or base64 with an encoding marker when it is not valid UTF-8.
+fn body_content(bytes: &[u8]) -> (String, Option<&'static str>) {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => (text.to_string(), None),
+        Err(_) => (general_purpose::STANDARD.encode(bytes), Some("base64")),
+    }
+}
+
+/// Collects every exchange of a scan and writes them out as HAR or NDJSON once it finishes.
+struct Exporter {
+    format: ExportFormat,
+    include_bodies: bool,
+    entries: Vec<(DateTime<Utc>, Value)>,
+}
+
+impl Exporter {
+    fn new(path: &str, format: Option<ExportFormat>, include_bodies: bool) -> Self {
+        let format = format.unwrap_or_else(|| {
+            let is_har = Path::new(path)
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("har"));
+            if is_har {
+                ExportFormat::Har
+            } else {
+                ExportFormat::Ndjson
+            }
+        });
+
+        Exporter {
+            format,
+            include_bodies,
+            entries: Vec::new(),
+        }
+    }
+
+    fn record(&mut self, probe: &Probe, template: &RequestTemplate) {
+        let entries = match self.format {
+            ExportFormat::Har => self.har_entries(probe, template),
+            ExportFormat::Ndjson => vec![(probe.started, self.ndjson_entry(probe, template))],
+        };
+        self.entries.extend(entries);
+    }
+
+    fn request_headers(probe: &Probe, template: &RequestTemplate) -> Vec<(String, String)> {
+        match Url::parse(&probe.job.url) {
+            Ok(url) => template.request_headers(&url, true),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// One entry per exchange, so each redirect hop is paired with its own response rather than
+    /// the request being paired with whatever the last hop returned.
+    fn har_entries(&self, probe: &Probe, template: &RequestTemplate) -> Vec<(DateTime<Utc>, Value)> {
+        let response = match &probe.result {
+            Ok(response) if !response.hops.is_empty() => response,
+            _ => return vec![(probe.started, self.har_entry(probe, template))],
+        };
+
+        let origin = Url::parse(&probe.job.url).ok();
+        let mut started = probe.started;
+        let mut entries = Vec::new();
+        for (index, hop) in response.hops.iter().enumerate() {
+            let url = Url::parse(&hop.url).ok();
+            let with_auth = url.as_ref().map(Url::host_str) == origin.as_ref().map(Url::host_str);
+            let headers = url
+                .map(|url| template.request_headers(&url, with_auth))
+                .unwrap_or_default();
+            let body = template.body.as_deref().filter(|_| hop.sent_body);
+            let request = self.har_request(hop.method.as_str(), &hop.url, &hop.http_version, &headers, body);
+
+            // Only the final hop's body is kept; redirect bodies are reported by size alone
+            let raw = (index + 1 == response.hops.len()).then_some(response.raw.as_slice());
+            let redirect_url = hop.location.as_deref().filter(|_| (300..400).contains(&hop.status));
+            let response = self.har_response(hop.status, &hop.http_version, &hop.headers, hop.size, raw, redirect_url);
+
+            entries.push((
+                started,
+                json!({
+                    "startedDateTime": started.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                    "time": duration_ms(hop.timing.total),
+                    "request": request,
+                    "response": response,
+                    "cache": {},
+                    "timings": hop_timings(&hop.timing, Duration::ZERO),
+                }),
+            ));
+            started += chrono::Duration::from_std(hop.timing.total).unwrap_or_default();
+        }
+        entries
+    }
+
+    /// Entry for a request whose hops weren't traced, or that failed before a response came back.
+    fn har_entry(&self, probe: &Probe, template: &RequestTemplate) -> Value {
+        let http_version = probe.result.as_ref().map_or("HTTP/1.1", |r| r.http_version.as_str());
+        let request = self.har_request(
+            template.method.as_str(),
+            &probe.job.url,
+            http_version,
+            &Self::request_headers(probe, template),
+            template.body.as_deref(),
+        );
+
+        let (response, time, timings) = match &probe.result {
+            Ok(response) => {
+                let mut response_json = self.har_response(
+                    response.status,
+                    &response.http_version,
+                    &response.headers,
+                    response.size,
+                    Some(&response.raw),
+                    response.location.as_deref(),
+                );
+                if response.final_url != probe.job.url {
+                    response_json["_finalUrl"] = json!(response.final_url);
+                }
+                (response_json, duration_ms(response.elapsed), har_timings(response))
+            }
+            Err(e) => {
+                let response_json = json!({
+                    "status": 0,
+                    "statusText": "",
+                    "httpVersion": "",
+                    "cookies": [],
+                    "headers": [],
+                    "content": { "size": 0, "mimeType": "" },
+                    "redirectURL": "",
+                    "headersSize": -1,
+                    "bodySize": -1,
+                    "_error": e,
+                });
+                let timings = json!({ "send": 0.0, "wait": 0.0, "receive": 0.0 });
+                (response_json, 0.0, timings)
+            }
+        };
+
+        json!({
+            "startedDateTime": probe.started.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            "time": time,
+            "request": request,
+            "response": response,
+            "cache": {},
+            "timings": timings,
+        })
+    }
+
+    fn har_request(
+        &self,
+        method: &str,
+        url: &str,
+        http_version: &str,
+        headers: &[(String, String)],
+        body: Option<&[u8]>,
+    ) -> Value {
+        let query: Vec<Value> = Url::parse(url)
+            .map(|url| {
+                url.query_pairs()
+                    .map(|(name, value)| json!({ "name": name, "value": value }))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut request = json!({
+            "method": method,
+            "url": url,
+            "httpVersion": http_version,
+            "cookies": request_cookies(headers),
+            "headers": har_headers(headers),
+            "queryString": query,
+            "headersSize": -1,
+            "bodySize": body.map_or(0, <[u8]>::len),
+        });
+        if let Some(body) = body.filter(|_| self.include_bodies) {
+            let mime_type = header_value(headers, "content-type").unwrap_or("application/octet-stream");
+            request["postData"] = json!({
+                "mimeType": mime_type,
+                "text": String::from_utf8_lossy(body),
+            });
+        }
+        request
+    }
+
+    fn har_response(
+        &self,
+        status: u16,
+        http_version: &str,
+        headers: &[(String, String)],
+        size: usize,
+        raw: Option<&[u8]>,
+        redirect_url: Option<&str>,
+    ) -> Value {
+        let mut content = json!({
+            "size": size,
+            "mimeType": header_value(headers, "content-type").unwrap_or(""),
+        });
+        if let Some(raw) = raw.filter(|_| self.include_bodies) {
+            let (text, encoding) = body_content(raw);
+            content["text"] = json!(text);
+            if let Some(encoding) = encoding {
+                content["encoding"] = json!(encoding);
+            }
+        }
+
+        let status_text = reqwest::StatusCode::from_u16(status)
+            .ok()
+            .and_then(|s| s.canonical_reason())
+            .unwrap_or("");
+        json!({
+            "status": status,
+            "statusText": status_text,
+            "httpVersion": http_version,
+            "cookies": response_cookies(headers),
+            "headers": har_headers(headers),
+            "content": content,
+            "redirectURL": redirect_url.unwrap_or(""),
+            "headersSize": -1,
+            "bodySize": size,
+        })
+    }
+
+    fn ndjson_entry(&self, probe: &Probe, template: &RequestTemplate) -> Value {
+        let mut entry = json!({
+            "started": probe.started.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            "method": template.method.as_str(),
+            "url": probe.job.url,
+            "request_headers": har_headers(&Self::request_headers(probe, template)),
+        });
+        if self.include_bodies {
+            if let Some(body) = &template.body {
+                entry["request_body"] = json!(String::from_utf8_lossy(body));
+            }
+        }
+
+        match &probe.result {
+            Ok(response) => {
+                entry["status"] = json!(response.status);
+                entry["final_url"] = json!(response.final_url);
+                entry["redirect_url"] = json!(response.location);
+                entry["http_version"] = json!(response.http_version);
+                entry["response_headers"] = har_headers(&response.headers);
+                entry["size"] = json!(response.size);
+                entry["hash"] = json!(response.hash);
+                entry["time_ms"] = json!(duration_ms(response.elapsed));
+                entry["timings"] = har_timings(response);
+                if self.include_bodies {
+                    let (text, encoding) = body_content(&response.raw);
+                    entry["body"] = json!(text);
+                    if let Some(encoding) = encoding {
+                        entry["body_encoding"] = json!(encoding);
+                    }
+                }
+            }
+            Err(e) => entry["error"] = json!(e),
+        }
+
+        entry
+    }
+
+    /// Writes entries in the order the requests were started.
+    fn write(mut self, path: &str) -> io::Result<()> {
+        self.entries.sort_by_key(|(started, _)| *started);
+        let entries = self.entries.into_iter().map(|(_, entry)| entry);
+
+        match self.format {
+            ExportFormat::Har => {
+                let har = json!({
+                    "log": {
+                        "version": "1.2",
+                        "creator": { "name": "http-status", "version": env!("CARGO_PKG_VERSION") },
+                        "pages": [],
+                        "entries": entries.collect::<Vec<_>>(),
+                    }
+                });
+                fs::write(path, serde_json::to_string_pretty(&har)?)
+            }
+            ExportFormat::Ndjson => {
+                let mut file = io::BufWriter::new(File::create(path)?);
+                for entry in entries {
+                    writeln!(file, "{}", entry)?;
+                }
+                file.flush()
+            }
+        }
     }
 }
 
-#[tokio::main]
-async fn main() {
-    let args: Vec<String> = env::args().collect();
-    
-    if args.len() < 3 {
-        eprintln!("Usage: {} <base_url> <paths_file>", args[0]);
-        std::process::exit(1);
-    }
-    
-    let base_url = &args[1];
-    let file_path = &args[2];
-    
-    // Read paths from file
-    let file = File::open(file_path).expect("Failed to open file");
-    let reader = BufReader::new(file);
-    let paths: Vec<String> = reader
-        .lines()
-        .filter_map(|line| line.ok())
+/// Requests sent by `bench` when neither a count nor a duration is given.
+const DEFAULT_BENCH_REQUESTS: u64 = 200;
+
+/// What one bench worker measured; merged into the final report. Requests that never got a
+/// complete response are kept apart from HTTP outcomes so they don't skew the response latencies.
+#[derive(Debug)]
+struct BenchStats {
+    latencies: Histogram<u64>,
+    outcomes: BTreeMap<String, u64>,
+    error_latencies: Histogram<u64>,
+    errors: BTreeMap<String, u64>,
+    bytes: u64,
+}
+
+impl BenchStats {
+    fn new() -> Self {
+        BenchStats {
+            latencies: latency_histogram(),
+            outcomes: BTreeMap::new(),
+            error_latencies: latency_histogram(),
+            errors: BTreeMap::new(),
+            bytes: 0,
+        }
+    }
+
+    fn record_response(&mut self, status: u16, latency: u64) {
+        self.latencies.saturating_record(latency.max(1));
+        *self.outcomes.entry(status.to_string()).or_default() += 1;
+    }
+
+    fn record_error(&mut self, kind: &str, latency: u64) {
+        self.error_latencies.saturating_record(latency.max(1));
+        *self.errors.entry(kind.to_string()).or_default() += 1;
+    }
+
+    fn total(&self) -> u64 {
+        self.outcomes.values().sum::<u64>() + self.errors.values().sum::<u64>()
+    }
+
+    /// Requests that errored or came back with a 4xx/5xx status.
+    fn failed(&self) -> u64 {
+        let bad_statuses: u64 = self
+            .outcomes
+            .iter()
+            .filter(|(status, _)| status.parse::<u16>().map_or(true, |status| status >= 400))
+            .map(|(_, count)| count)
+            .sum();
+        bad_statuses + self.errors.values().sum::<u64>()
+    }
+
+    fn merge(&mut self, other: BenchStats) {
+        let _ = self.latencies.add(&other.latencies);
+        let _ = self.error_latencies.add(&other.error_latencies);
+        for (outcome, count) in other.outcomes {
+            *self.outcomes.entry(outcome).or_default() += count;
+        }
+        for (kind, count) in other.errors {
+            *self.errors.entry(kind).or_default() += count;
+        }
+        self.bytes += other.bytes;
+    }
+}
+
+fn latency_histogram() -> Histogram<u64> {
+    // Microsecond resolution up to an hour with 3 significant digits
+    Histogram::new_with_bounds(1, 3_600_000_000, 3).expect("valid histogram bounds")
+}
+
+/// `min`, `p50`, `p90`, `p99` and `max` rows of a latency histogram recorded in microseconds.
+fn latency_rows(latencies: &Histogram<u64>) -> Vec<(&'static str, String)> {
+    if latencies.is_empty() {
+        return Vec::new();
+    }
+    let micros = |value: u64| format_ms(Duration::from_micros(value));
+    let mut rows = vec![("min", micros(latencies.min()))];
+    for (label, quantile) in [("p50", 0.5), ("p90", 0.9), ("p99", 0.99)] {
+        rows.push((label, micros(latencies.value_at_quantile(quantile))));
+    }
+    rows.push(("max", micros(latencies.max())));
+    rows
+}
+
+fn error_kind(error: &reqwest::Error) -> &'static str {
+    if error.is_timeout() {
+        "timeout"
+    } else if error.is_connect() {
+        "connect error"
+    } else if error.is_body() || error.is_decode() {
+        "body error"
+    } else if error.is_redirect() {
+        "redirect error"
+    } else {
+        "request error"
+    }
+}
+
+/// When a worker may claim request number `index`, and when it should stop.
+#[derive(Debug, Clone, Copy)]
+struct BenchSchedule {
+    start: Instant,
+    requests: Option<u64>,
+    deadline: Option<Instant>,
+    interval: Option<Duration>,
+}
+
+impl BenchSchedule {
+    /// Intended send time of the request, or `None` once the run is over.
+    fn slot(&self, index: u64) -> Option<Instant> {
+        if self.requests.is_some_and(|n| index >= n) {
+            return None;
+        }
+
+        let intended = match self.interval {
+            Some(interval) => self.start + interval.mul_f64(index as f64),
+            None => Instant::now(),
+        };
+        if self.deadline.is_some_and(|deadline| intended >= deadline) {
+            return None;
+        }
+        Some(intended)
+    }
+
+    /// Microseconds from the intended send time to `finished`. At a fixed rate the intended time
+    /// is the slot, not the moment the request actually went out, so time spent queued behind a
+    /// slow response still counts.
+    fn latency(&self, intended: Instant, finished: Instant) -> u64 {
+        finished.saturating_duration_since(intended).as_micros() as u64
+    }
+}
+
+/// Sends requests until the schedule runs out. In fixed-rate mode latency is measured from
+/// each request's intended send time, so a stalled server cannot hide its own queueing delay
+/// (coordinated omission).
+async fn bench_worker(
+    template: Arc<RequestTemplate>,
+    url: Arc<str>,
+    schedule: BenchSchedule,
+    next: Arc<AtomicU64>,
+) -> BenchStats {
+    let mut stats = BenchStats::new();
+
+    loop {
+        let index = next.fetch_add(1, Ordering::Relaxed);
+        let Some(intended) = schedule.slot(index) else {
+            break;
+        };
+        tokio::time::sleep_until(intended.into()).await;
+
+        match template.build(&url).send().await {
+            Ok(response) => {
+                let status = response.status().as_u16();
+                match response.bytes().await {
+                    Ok(body) => {
+                        stats.bytes += body.len() as u64;
+                        stats.record_response(status, schedule.latency(intended, Instant::now()));
+                    }
+                    Err(e) => stats.record_error(error_kind(&e), schedule.latency(intended, Instant::now())),
+                }
+            }
+            Err(e) => stats.record_error(error_kind(&e), schedule.latency(intended, Instant::now())),
+        }
+    }
+
+    stats
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.2} {}", value, UNITS[unit])
+}
+
+async fn run_bench(args: &BenchArgs) {
+    let template = Arc::new(
+        build_template(&args.request, &args.client, RedirectMode::Follow, false, Throttle::default())
+            .unwrap_or_else(|e| fail(e)),
+    );
+    if let Err(e) = Url::parse(&args.url) {
+        fail(format!("invalid URL '{}': {}", args.url, e));
+    }
+
+    let interval = match args.rate {
+        Some(rate) if rate > 0.0 => Some(Duration::from_secs_f64(1.0 / rate)),
+        Some(_) => fail("rate must be greater than zero".to_string()),
+        None => None,
+    };
+    let requests = match args.duration {
+        Some(_) => args.requests,
+        None => Some(args.requests.unwrap_or(DEFAULT_BENCH_REQUESTS)),
+    };
+
+    match (requests, args.duration) {
+        (Some(n), _) => println!("Sending {} requests to {}", n, args.url),
+        (None, Some(duration)) => println!("Sending requests to {} for {}s", args.url, duration.as_secs()),
+        (None, None) => {}
+    }
+    match args.rate {
+        Some(rate) => println!("Fixed rate of {} req/s with up to {} in flight", rate, args.client.concurrency),
+        None => println!("{} concurrent connections", args.client.concurrency),
+    }
+
+    let start = Instant::now();
+    let schedule = BenchSchedule {
+        start,
+        requests,
+        deadline: args.duration.map(|duration| start + duration),
+        interval,
+    };
+    let url: Arc<str> = Arc::from(args.url.as_str());
+    let next = Arc::new(AtomicU64::new(0));
+
+    let mut workers = JoinSet::new();
+    for _ in 0..args.client.concurrency {
+        workers.spawn(bench_worker(Arc::clone(&template), Arc::clone(&url), schedule, Arc::clone(&next)));
+    }
+
+    let mut stats = BenchStats::new();
+    while let Some(joined) = workers.join_next().await {
+        if let Ok(worker_stats) = joined {
+            stats.merge(worker_stats);
+        }
+    }
+    let elapsed = start.elapsed();
+
+    println!();
+    println!("{}", "Summary:".bold());
+    let total = stats.total();
+    println!("  {:14} {} ({} failed)", "Requests:", total, stats.failed());
+    println!("  {:14} {:.2}s", "Duration:", elapsed.as_secs_f64());
+    println!("  {:14} {:.2} req/s", "Throughput:", total as f64 / elapsed.as_secs_f64());
+    println!("  {:14} {}", "Transferred:", format_bytes(stats.bytes));
+
+    let rows = latency_rows(&stats.latencies);
+    if !rows.is_empty() {
+        println!();
+        println!("{}", "Latency:".bold());
+        for (label, value) in rows {
+            println!("  {:6} {}", label, value);
+        }
+    }
+
+    if !stats.outcomes.is_empty() {
+        println!();
+        println!("{}", "Responses:".bold());
+        for (outcome, count) in &stats.outcomes {
+            let label = match outcome.parse::<u16>() {
+                Ok(status) if status < 400 => outcome.green(),
+                _ => outcome.yellow(),
+            };
+            println!("  {:16} {}", label, count);
+        }
+    }
+
+    if !stats.errors.is_empty() {
+        println!();
+        println!("{}", "Errors:".bold());
+        for (kind, count) in &stats.errors {
+            println!("  {:16} {}", kind.red(), count);
+        }
+        println!();
+        println!("{}", "Error latency:".bold());
+        for (label, value) in latency_rows(&stats.error_latencies) {
+            println!("  {:6} {}", label, value);
+        }
+    }
+}
+
+/// Latency samples kept per URL for the rolling statistics in watch mode.
+const LATENCY_WINDOW: usize = 20;
+
+/// Per-URL state carried across watch rounds.
+#[derive(Debug, Default)]
+struct UrlHealth {
+    up: Option<bool>,
+    latencies: VecDeque<Duration>,
+    checks: u64,
+    up_checks: u64,
+}
+
+impl UrlHealth {
+    fn record(&mut self, up: bool, latency: Duration) {
+        self.up = Some(up);
+        self.checks += 1;
+        if up {
+            self.up_checks += 1;
+        }
+        if self.latencies.len() == LATENCY_WINDOW {
+            self.latencies.pop_front();
+        }
+        self.latencies.push_back(latency);
+    }
+
+    fn uptime(&self) -> f64 {
+        if self.checks == 0 {
+            return 100.0;
+        }
+        self.up_checks as f64 / self.checks as f64 * 100.0
+    }
+
+    fn latency_summary(&self) -> String {
+        let Some(min) = self.latencies.iter().min() else {
+            return "-".to_string();
+        };
+        let max = self.latencies.iter().max().unwrap_or(min);
+        let avg = self.latencies.iter().sum::<Duration>() / self.latencies.len() as u32;
+        format!("min {} / avg {} / max {}", format_ms(*min), format_ms(avg), format_ms(*max))
+    }
+}
+
+/// One line of the watch history file.
+#[derive(Debug, Serialize, Deserialize)]
+struct HistoryEntry {
+    timestamp: String,
+    url: String,
+    up: bool,
+    status: Option<u16>,
+    latency_ms: f64,
+}
+
+/// Rebuilds uptime counters and last known states from an existing history file.
+fn load_history(path: &str) -> HashMap<String, UrlHealth> {
+    let mut health: HashMap<String, UrlHealth> = HashMap::new();
+    let Ok(file) = File::open(path) else {
+        return health;
+    };
+
+    for line in BufReader::new(file).lines().map_while(Result::ok) {
+        let Ok(entry) = serde_json::from_str::<HistoryEntry>(&line) else {
+            continue;
+        };
+        let state = health.entry(entry.url).or_default();
+        state.up = Some(entry.up);
+        state.checks += 1;
+        if entry.up {
+            state.up_checks += 1;
+        }
+    }
+
+    health
+}
+
+fn notify_change(url: &str, up: bool, detail: &str) {
+    let (summary, icon) = if up {
+        ("Site is back up", "network-transmit-receive")
+    } else {
+        ("Site is down", "network-error")
+    };
+
+    if let Err(e) = Notification::new()
+        .summary(summary)
+        .body(&format!("{}\n{}", url, detail))
+        .icon(icon)
+        .timeout(5000)
+        .show() {
+        eprintln!("Failed to show notification: {}", e);
+    }
+}
+
+fn state_name(up: bool) -> &'static str {
+    if up {
+        "up"
+    } else {
+        "down"
+    }
+}
+
+fn run_hook(command: &str, url: &str, up: bool, previous: bool, detail: &str) {
+    let child = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("HTTP_STATUS_URL", url)
+        .env("HTTP_STATUS_STATE", state_name(up))
+        .env("HTTP_STATUS_PREVIOUS_STATE", state_name(previous))
+        .env("HTTP_STATUS_DETAIL", detail)
+        .status();
+
+    tokio::spawn(async move {
+        if let Err(e) = child.await {
+            eprintln!("Failed to run hook: {}", e);
+        }
+    });
+}
+
+async fn timed_check(template: Arc<RequestTemplate>, job: Job) -> (Probe, Duration) {
+    let start = Instant::now();
+    let probe = check_url(template, job).await;
+    (probe, start.elapsed())
+}
+
+/// Checks the URL list forever, reporting state changes and rolling latency.
+async fn run_watch(args: &Args, base_url: &str, paths_file: &str, interval: Duration) {
+    let template = url_list_template(args, args.verbose);
+
+    let words = read_words(paths_file).unwrap_or_else(|e| fail(format!("failed to open '{}': {}", paths_file, e)));
+    let base_url = format!("{}/", base_url.trim_end_matches('/'));
+    let urls: Vec<String> = expand_words(&words, &args.extensions)
+        .iter()
+        .map(|candidate| format!("{}{}", base_url, candidate))
         .collect();
-    
-    // Create concurrent tasks with limit
-    let mut tasks = JoinSet::new();
-    const MAX_CONCURRENT: usize = 10;
-    
-    for path in paths {
-        let url = format!("{}/{}", base_url.trim_end_matches('/'), path);
-        
-        // Limit concurrent tasks
-        while tasks.len() >= MAX_CONCURRENT {
-            if let Some(result) = tasks.join_next().await {
-                if let Ok(status) = result {
-                    println!("{}", status);
+
+    let mut health = args.history.as_deref().map(load_history).unwrap_or_default();
+    let mut history = args.history.as_ref().map(|path| {
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap_or_else(|e| fail(format!("failed to open history '{}': {}", path, e)))
+    });
+
+    println!("Watching {} URLs every {}s. Press Ctrl+C to stop", urls.len(), interval.as_secs());
+
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        ticker.tick().await;
+        let now = chrono::Local::now();
+        let mut results = Vec::with_capacity(urls.len());
+        let mut tasks = JoinSet::new();
+
+        for url in &urls {
+            while tasks.len() >= args.client.concurrency {
+                if let Some(Ok(result)) = tasks.join_next().await {
+                    results.push(result);
+                }
+            }
+            let job = Job { url: url.clone(), dir: base_url.clone(), depth: 0 };
+            tasks.spawn(timed_check(Arc::clone(&template), job));
+        }
+        while let Some(joined) = tasks.join_next().await {
+            if let Ok(result) = joined {
+                results.push(result);
+            }
+        }
+        results.sort_by(|a, b| a.0.job.url.cmp(&b.0.job.url));
+
+        println!();
+        println!("{}", now.format("%Y-%m-%d %H:%M:%S").to_string().bold());
+
+        for (probe, latency) in results {
+            let url = probe.job.url;
+            let (up, status, detail) = match &probe.result {
+                Ok(response) => {
+                    let up = if args.match_code.is_empty() {
+                        response.status < 400
+                    } else {
+                        args.match_code.iter().any(|r| r.contains(response.status))
+                    };
+                    (up, Some(response.status), format!("status {}", response.status))
+                }
+                Err(e) => (false, None, e.clone()),
+            };
+
+            let state = health.entry(url.clone()).or_default();
+            let previous = state.up;
+            state.record(up, latency);
+
+            let label = if up { "UP".green() } else { "DOWN".red() };
+            println!(
+                "  {} {} - {} ({}, {}, uptime {:.2}%)",
+                label,
+                url,
+                detail,
+                format_ms(latency),
+                state.latency_summary(),
+                state.uptime()
+            );
+
+            if let Some(previous) = previous.filter(|&previous| previous != up) {
+                println!("    {} {} -> {}", "state changed:".yellow(), state_name(previous), state_name(up));
+                if args.notify {
+                    notify_change(&url, up, &detail);
+                }
+                if let Some(command) = &args.on_change {
+                    run_hook(command, &url, up, previous, &detail);
+                }
+            }
+
+            if let Some(file) = history.as_mut() {
+                let entry = HistoryEntry {
+                    timestamp: now.to_rfc3339(),
+                    url,
+                    up,
+                    status,
+                    latency_ms: latency.as_secs_f64() * 1000.0,
+                };
+                if let Err(e) = writeln!(file, "{}", serde_json::to_string(&entry).unwrap_or_default()) {
+                    eprintln!("Failed to write history: {}", e);
                 }
             }
         }
-        
-        tasks.spawn(check_url(url));
     }
-    
-    // Collect results
-    while let Some(result) = tasks.join_next().await {
-        if let Ok(status) = result {
-            println!("{}", status);
+}
+
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
+
+    match &args.command {
+        Some(Command::Check(check)) => {
+            if !run_suite(check).await {
+                std::process::exit(1);
+            }
+        }
+        Some(Command::Crawl(crawl)) => {
+            if !run_crawl(crawl).await {
+                std::process::exit(1);
+            }
+        }
+        Some(Command::Bench(bench)) => run_bench(bench).await,
+        None => {
+            // Both are required by clap whenever no subcommand is given
+            let base_url = args.base_url.as_deref().unwrap_or_default();
+            let paths_file = args.paths_file.as_deref().unwrap_or_default();
+            match args.watch {
+                Some(interval) => run_watch(&args, base_url, paths_file, interval).await,
+                None => run_scan(&args, base_url, paths_file).await,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(status: u16, body: &str) -> Response {
+        let head = ResponseHead {
+            status,
+            final_url: String::new(),
+            location: None,
+            http_version: "HTTP/1.1".into(),
+            headers: Vec::new(),
+            ttfb: Duration::ZERO,
+        };
+        Response::new(head, body.as_bytes().to_vec(), Duration::ZERO, Vec::new())
+    }
+
+    fn not_found_page(url: &str) -> String {
+        let path = &url["http://test".len()..];
+        let word = path.trim_end_matches('/').rsplit('/').next().unwrap_or_default();
+        format!(
+            "<html><body><h1>Sorry</h1><p>Sorry, the page {} was not found.</p><p>You asked for {}. Back to admin home.</p></body></html>",
+            path, word
+        )
+    }
+
+    #[test]
+    fn soft_404_matches_words_that_also_appear_in_the_template() {
+        let probe = "http://test/k2j4h5g6f7d8s9a0";
+        let baseline = Fingerprint::from_response(probe, &response(200, &not_found_page(probe)));
+
+        for word in ["html", "Sorry", "admin", "admin/admin", "body", "p"] {
+            let url = format!("http://test/{}", word);
+            assert!(baseline.matches(&url, &response(200, &not_found_page(&url))), "{} should match the baseline", word);
+        }
+    }
+
+    #[test]
+    fn soft_404_keeps_real_pages_and_other_statuses() {
+        let probe = "http://test/k2j4h5g6f7d8s9a0";
+        let baseline = Fingerprint::from_response(probe, &response(200, &not_found_page(probe)));
+
+        let real = response(200, "<html><body><h1>Admin console</h1><p>Welcome back, 3 pending jobs.</p></body></html>");
+        assert!(!baseline.matches("http://test/admin", &real));
+        assert!(!baseline.matches("http://test/html", &response(403, &not_found_page("http://test/html"))));
+    }
+
+    #[test]
+    fn directories_need_a_status_that_shows_they_exist() {
+        assert_eq!(discovered_directory("http://test/real/", &response(200, "")), Some("http://test/real/".into()));
+        assert_eq!(discovered_directory("http://test/private/", &response(403, "")), Some("http://test/private/".into()));
+        assert_eq!(discovered_directory("http://test/ghost/", &response(404, "")), None);
+        assert_eq!(discovered_directory("http://test/broken/", &response(500, "")), None);
+
+        let mut moved = response(301, "");
+        moved.location = Some("http://test/real/".into());
+        assert_eq!(discovered_directory("http://test/real", &moved), Some("http://test/real/".into()));
+        moved.location = Some("http://test/login".into());
+        assert_eq!(discovered_directory("http://test/real", &moved), None);
+
+        let mut followed = response(200, "");
+        followed.final_url = "http://test/real/".into();
+        assert_eq!(discovered_directory("http://test/real", &followed), Some("http://test/real/".into()));
+        followed.status = 404;
+        assert_eq!(discovered_directory("http://test/real", &followed), None);
+    }
+
+    #[test]
+    fn normalize_body_only_replaces_standalone_words() {
+        let body = normalize_body("admins see /admin/admin and admin-panel or xadmin", &["http://test/admin/admin"]);
+        assert_eq!(body, "admins see {path} and {word}-panel or xadmin");
+        assert_eq!(replace_standalone("<html>html5 html", "html", "W"), "<W>html5 W");
+    }
+
+    const SUITE_TOML: &str = r#"
+base_url = "http://localhost:8080"
+headers = { "X-Env" = "test" }
+
+[[checks]]
+name = "health"
+url = "/health"
+status = [200]
+json = { "/status" = "ok", "/version" = 2 }
+max_latency_ms = 500
+
+[[checks]]
+url = "/admin"
+method = "HEAD"
+follow_redirects = false
+"#;
+
+    #[test]
+    fn suites_parse_from_toml_and_json() {
+        let suite = parse_suite("suite.toml", SUITE_TOML).unwrap();
+        assert_eq!(suite.base_url.as_deref(), Some("http://localhost:8080"));
+        assert_eq!(suite.headers["X-Env"], "test");
+        assert_eq!(suite.checks.len(), 2);
+        assert_eq!(suite.checks[0].display_name(), "health");
+        assert_eq!(suite.checks[0].json["/version"], json!(2));
+        assert_eq!(suite.checks[0].max_latency_ms, Some(500));
+        assert!(suite.checks[0].follow_redirects);
+        assert_eq!(suite.checks[1].display_name(), "HEAD /admin");
+        assert!(!suite.checks[1].follow_redirects);
+
+        let json = r#"{"checks": [{"url": "https://example.com/", "body_regex": "Welcome"}]}"#;
+        let suite = parse_suite("suite.JSON", json).unwrap();
+        assert_eq!(suite.checks[0].method, "GET");
+        assert!(suite.checks[0].status.is_empty());
+        assert_eq!(suite.checks[0].body_regex.as_deref(), Some("Welcome"));
+
+        assert!(parse_suite("suite.json", SUITE_TOML).is_err());
+        assert!(parse_suite("suite.toml", "[[checks]]\nname = \"no url\"").is_err());
+    }
+
+    fn spec(toml: &str) -> CheckSpec {
+        parse_suite("suite.toml", &format!("[[checks]]\nurl = \"/\"\n{}", toml)).unwrap().checks.remove(0)
+    }
+
+    #[test]
+    fn status_assertions_default_to_any_2xx() {
+        let any = spec("");
+        assert!(evaluate_check(&any, None, 204, "", Duration::ZERO).is_empty());
+        assert_eq!(evaluate_check(&any, None, 301, "", Duration::ZERO), ["unexpected status 301"]);
+
+        let listed = spec("status = [301, 404]");
+        assert!(evaluate_check(&listed, None, 404, "", Duration::ZERO).is_empty());
+        assert_eq!(evaluate_check(&listed, None, 200, "", Duration::ZERO), ["unexpected status 200"]);
+    }
+
+    #[test]
+    fn body_regex_and_latency_assertions() {
+        let check = spec("max_latency_ms = 100");
+        let regex = Regex::new("Welcome, \\w+").unwrap();
+        assert!(evaluate_check(&check, Some(&regex), 200, "<h1>Welcome, ann</h1>", Duration::from_millis(100)).is_empty());
+
+        let failures = evaluate_check(&check, Some(&regex), 200, "Goodbye", Duration::from_millis(101));
+        assert_eq!(failures.len(), 2);
+        assert!(failures[0].starts_with("latency "));
+        assert!(failures[0].ends_with("exceeds 100ms"));
+        assert_eq!(failures[1], "body does not match /Welcome, \\w+/");
+    }
+
+    #[test]
+    fn json_pointer_assertions() {
+        let check = spec(r#"json = { "/status" = "ok", "/items/1/id" = 7, "/missing" = true }"#);
+        let failures = evaluate_check(&check, None, 200, r#"{"status":"degraded","items":[{"id":1},{"id":7}]}"#, Duration::ZERO);
+        assert_eq!(failures, ["/missing is missing", "/status is \"degraded\", expected \"ok\""]);
+
+        let failures = evaluate_check(&check, None, 200, "<html>", Duration::ZERO);
+        assert_eq!(failures.len(), 1);
+        assert!(failures[0].starts_with("body is not valid JSON"));
+    }
+
+    #[test]
+    fn junit_report_escapes_names_and_messages() {
+        let results = vec![
+            CheckResult { name: "ok <fast>".into(), latency: Duration::from_millis(5), failures: Vec::new(), error: None },
+            CheckResult {
+                name: "a & \"b\"".into(),
+                latency: Duration::ZERO,
+                failures: vec!["/x is 'y', expected <z>".into(), "second".into()],
+                error: None,
+            },
+            CheckResult { name: "down".into(), latency: Duration::ZERO, failures: Vec::new(), error: Some("refused <tcp>".into()) },
+        ];
+        let xml = junit_report("suite & co", &results, Duration::from_millis(1500));
+
+        assert!(xml.contains(r#"<testsuite name="suite &amp; co" tests="3" failures="1" errors="1" time="1.500">"#));
+        assert!(xml.contains(r#"<testcase name="ok &lt;fast&gt;" classname="http-status" time="0.005"/>"#));
+        assert!(xml.contains(r#"<testcase name="a &amp; &quot;b&quot;""#));
+        assert!(xml.contains(
+            r#"<failure message="/x is &apos;y&apos;, expected &lt;z&gt;">/x is &apos;y&apos;, expected &lt;z&gt;"#
+        ));
+        assert!(xml.contains(r#"<error message="refused &lt;tcp&gt;"/>"#));
+    }
+
+    fn robots_allow(robots: &Robots, path: &str) -> bool {
+        robots.allows(&Url::parse("https://example.com").unwrap().join(path).unwrap())
+    }
+
+    #[test]
+    fn robots_groups_match_our_product_token_exactly() {
+        let text = "User-agent: h\nDisallow: /fragment\n\nUser-agent: HTTP-Status\nDisallow: /private\n\nUser-agent: *\nDisallow: /\n";
+        let robots = Robots::parse(text, "http-status/1.0");
+        assert!(!robots_allow(&robots, "/private/x"));
+        assert!(robots_allow(&robots, "/fragment"));
+        assert!(robots_allow(&robots, "/public"));
+    }
+
+    #[test]
+    fn robots_fall_back_to_wildcard_only_when_no_group_names_us() {
+        let text = "User-agent: http\nUser-agent: status\nDisallow: /a\n\nUser-agent: *\nDisallow: /b\nAllow: /b/open\n";
+        let robots = Robots::parse(text, "http-status/1.0");
+        assert!(robots_allow(&robots, "/a"));
+        assert!(!robots_allow(&robots, "/b/closed"));
+        assert!(robots_allow(&robots, "/b/open"));
+
+        // A group naming us with an empty Disallow allows everything, even if `*` is stricter.
+        let robots = Robots::parse("User-agent: *\nDisallow: /\n\nUser-agent: http-status\nDisallow:\n", "http-status/1.0");
+        assert!(robots_allow(&robots, "/anything"));
+    }
+
+    /// Minimal HTTP/1.1 stand-in: one request per connection, responses chosen by path.
+    async fn stand_in_server() -> Url {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let flaky_hits = Arc::new(AtomicU64::new(0));
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else { return };
+                let flaky_hits = Arc::clone(&flaky_hits);
+                tokio::spawn(async move {
+                    let mut request = Vec::new();
+                    let mut buffer = [0u8; 1024];
+                    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                        match socket.read(&mut buffer).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => request.extend_from_slice(&buffer[..n]),
+                        }
+                    }
+                    let request = String::from_utf8_lossy(&request);
+                    let path = request.split_whitespace().nth(1).unwrap_or("/");
+                    let (status, headers, body) = match path {
+                        "/health" => ("200 OK", "Content-Type: application/json\r\n", r#"{"status":"ok","version":2}"#),
+                        "/admin" => ("301 Moved Permanently", "Location: /login\r\n", ""),
+                        "/login" => ("200 OK", "", "Welcome, please sign in"),
+                        "/flaky" if flaky_hits.fetch_add(1, Ordering::SeqCst) == 0 => ("503 Service Unavailable", "", "busy"),
+                        "/flaky" => ("200 OK", "", "recovered"),
+                        _ => ("404 Not Found", "", "nope"),
+                    };
+                    let response = format!(
+                        "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        status,
+                        headers,
+                        body.len(),
+                        body
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        Url::parse(&format!("http://{}/", address)).unwrap()
+    }
+
+    async fn check_against(base: &Url, client: &Client, throttle: Throttle, toml: &str) -> CheckResult {
+        let spec = parse_suite("suite.toml", &format!("[[checks]]\n{}", toml)).unwrap().checks.remove(0);
+        run_check(client.clone(), Arc::new(throttle), Arc::default(), Some(base.clone()), spec).await
+    }
+
+    #[tokio::test]
+    async fn checks_run_against_a_local_server() {
+        let base = stand_in_server().await;
+        let client = Client::new();
+        let no_redirects = Client::builder().redirect(Policy::none()).build().unwrap();
+
+        let health = r#"url = "/health"
+json = { "/status" = "ok", "/version" = 2 }
+max_latency_ms = 2000"#;
+        let result = check_against(&base, &client, Throttle::default(), health).await;
+        assert!(result.passed(), "{:?}", result);
+
+        let result = check_against(&base, &client, Throttle::default(), "url = \"/admin\"\nbody_regex = \"Welcome\"").await;
+        assert!(result.passed(), "{:?}", result);
+
+        let result = check_against(&base, &no_redirects, Throttle::default(), "url = \"/admin\"\nstatus = [301]").await;
+        assert!(result.passed(), "{:?}", result);
+
+        let result = check_against(&base, &client, Throttle::default(), "url = \"/nothing\"").await;
+        assert_eq!(result.failures, ["unexpected status 404"]);
+    }
+
+    #[tokio::test]
+    async fn check_latency_excludes_retry_backoff() {
+        let base = stand_in_server().await;
+        let retry = RetryArgs { retries: 1, backoff: 1000, rate: None, per_host: None };
+
+        let result = check_against(&base, &Client::new(), Throttle::new(&retry), "url = \"/flaky\"\nmax_latency_ms = 400").await;
+        assert!(result.passed(), "{:?}", result);
+        assert!(result.latency < Duration::from_millis(400));
+    }
+
+    fn fixed_rate(start: Instant, requests: Option<u64>, deadline: Option<Instant>) -> BenchSchedule {
+        BenchSchedule {
+            start,
+            requests,
+            deadline,
+            interval: Some(Duration::from_millis(100)),
+        }
+    }
+
+    #[test]
+    fn fixed_rate_schedule_spaces_slots_from_the_start() {
+        let start = Instant::now();
+        let schedule = fixed_rate(start, Some(3), None);
+        assert_eq!(schedule.slot(0), Some(start));
+        assert_eq!(schedule.slot(1), Some(start + Duration::from_millis(100)));
+        assert_eq!(schedule.slot(2), Some(start + Duration::from_millis(200)));
+        assert_eq!(schedule.slot(3), None);
+
+        let schedule = fixed_rate(start, None, Some(start + Duration::from_millis(250)));
+        assert_eq!(schedule.slot(2), Some(start + Duration::from_millis(200)));
+        assert_eq!(schedule.slot(3), None);
+    }
+
+    #[test]
+    fn fixed_rate_latency_includes_time_queued_past_the_slot() {
+        // A worker stuck behind a slow response picks up slot 0 a second late; the request
+        // itself then takes 50ms. The recorded latency must include the second it waited.
+        let start = Instant::now();
+        let schedule = fixed_rate(start, None, None);
+        let finished = start + Duration::from_millis(1050);
+
+        assert_eq!(schedule.latency(schedule.slot(0).unwrap(), finished), 1_050_000);
+        assert_eq!(schedule.latency(schedule.slot(10).unwrap(), finished), 50_000);
+        assert_eq!(schedule.latency(schedule.slot(20).unwrap(), finished), 0);
+    }
+
+    #[test]
+    fn latency_rows_report_min_percentiles_and_max() {
+        assert!(latency_rows(&latency_histogram()).is_empty());
+
+        let mut latencies = latency_histogram();
+        for micros in (10..=1000).step_by(10) {
+            latencies.record(micros).unwrap();
+        }
+        let rows: Vec<String> = latency_rows(&latencies)
+            .into_iter()
+            .map(|(label, value)| format!("{} {}", label, value))
+            .collect();
+        assert_eq!(
+            rows,
+            ["min 0.01ms", "p50 0.50ms", "p90 0.90ms", "p99 0.99ms", "max 1.00ms"]
+        );
+    }
+
+    #[test]
+    fn bench_stats_keep_errors_apart_from_responses() {
+        let mut stats = BenchStats::new();
+        stats.record_response(200, 800);
+        stats.record_response(503, 900);
+
+        let mut other = BenchStats::new();
+        other.record_response(200, 700);
+        other.record_error("timeout", 1_500);
+        other.record_error("connect error", 0);
+        stats.merge(other);
+
+        assert_eq!(stats.total(), 5);
+        assert_eq!(stats.failed(), 3);
+        assert_eq!(
+            stats.outcomes,
+            BTreeMap::from([("200".to_string(), 2), ("503".to_string(), 1)])
+        );
+        assert_eq!(
+            stats.errors,
+            BTreeMap::from([("connect error".to_string(), 1), ("timeout".to_string(), 1)])
+        );
+        assert_eq!((stats.latencies.len(), stats.latencies.max()), (3, 900));
+        assert_eq!((stats.error_latencies.len(), stats.error_latencies.min()), (2, 1));
+        assert_eq!(stats.error_latencies.max(), 1_500);
+    }
+
+    fn hop(url: &str, status: u16, location: Option<&str>, headers: &[(&str, &str)]) -> Hop {
+        let millis = Duration::from_millis;
+        Hop {
+            method: Method::GET,
+            url: url.into(),
+            sent_body: false,
+            status,
+            location: location.map(str::to_string),
+            server: None,
+            size: 0,
+            http_version: "HTTP/1.1".into(),
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            timing: Timing {
+                dns: millis(1),
+                connect: millis(1),
+                tls: None,
+                ttfb: millis(3),
+                total: millis(4),
+            },
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn har_export_has_one_entry_per_redirect_hop() {
+        let cli = "http-status http://test words.txt -b session=abc -b theme=dark";
+        let args = Args::try_parse_from(cli.split(' ')).unwrap();
+        let template = build_template(
+            &args.request,
+            &args.client,
+            RedirectMode::Follow,
+            true,
+            Throttle::default(),
+        )
+        .unwrap();
+
+        let mut response = response(200, "welcome");
+        response.hops = vec![
+            hop("http://test/real", 301, Some("http://test/real/"), &[]),
+            hop(
+                "http://test/real/",
+                200,
+                None,
+                &[("set-cookie", "visited=1; Path=/; HttpOnly")],
+            ),
+        ];
+        let probe = Probe {
+            job: Job {
+                url: "http://test/real".into(),
+                dir: "http://test/".into(),
+                depth: 0,
+            },
+            started: Utc::now(),
+            result: Ok(response),
+        };
+
+        let exporter = Exporter::new("scan.har", None, true);
+        let entries: Vec<Value> = exporter
+            .har_entries(&probe, &template)
+            .into_iter()
+            .map(|(_, entry)| entry)
+            .collect();
+        assert_eq!(entries.len(), 2);
+
+        assert_eq!(entries[0]["request"]["url"], "http://test/real");
+        assert_eq!(entries[0]["response"]["status"], 301);
+        assert_eq!(entries[0]["response"]["redirectURL"], "http://test/real/");
+        assert!(entries[0]["response"]["content"].get("text").is_none());
+        assert_eq!(
+            entries[0]["request"]["cookies"],
+            json!([{ "name": "session", "value": "abc" }, { "name": "theme", "value": "dark" }])
+        );
+
+        assert_eq!(entries[1]["request"]["url"], "http://test/real/");
+        assert_eq!(entries[1]["response"]["status"], 200);
+        assert_eq!(entries[1]["response"]["redirectURL"], "");
+        assert_eq!(entries[1]["response"]["content"]["text"], "welcome");
+        assert_eq!(
+            entries[1]["response"]["cookies"],
+            json!([{ "name": "visited", "value": "1" }])
+        );
+        assert!(entries[0]["startedDateTime"].as_str() < entries[1]["startedDateTime"].as_str());
+    }
+}